@@ -1,4 +1,5 @@
 use bevy::{prelude::*, window::WindowId, winit::WinitWindows};
+use my_game::LevelSeed;
 use std::io::Cursor;
 use winit::window::Icon;
 
@@ -25,6 +26,12 @@ fn main() {
     let mut app = my_game::app();
 
     info!("Starting launcher: Native");
+    if let Some(seed) = std::env::var("FLOCK_SEED")
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+    {
+        app.insert_resource(LevelSeed(seed));
+    }
     app.add_startup_system(set_window_icon);
     app.run();
 }
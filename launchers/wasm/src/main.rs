@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use my_game::LAUNCHER_TITLE;
+use my_game::{LevelSeed, LAUNCHER_TITLE};
 
 fn set_window_title(title: &str) {
     web_sys::window()
@@ -8,10 +8,26 @@ fn set_window_title(title: &str) {
         .set_title(title);
 }
 
+/// Reads the level seed from a `?seed=` query parameter, if present.
+fn query_seed() -> Option<u64> {
+    web_sys::window()?
+        .location()
+        .search()
+        .ok()?
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("seed="))?
+        .parse()
+        .ok()
+}
+
 fn main() {
     // Start the Bevy App
     set_window_title(LAUNCHER_TITLE);
     let mut app = my_game::app();
     info!("Starting launcher: WASM");
+    if let Some(seed) = query_seed() {
+        app.insert_resource(LevelSeed(seed));
+    }
     app.run();
 }
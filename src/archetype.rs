@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+
+use crate::flocking::FlockingConfig;
+
+/// A sheep's temperament, which scales its share of the flocking config.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SheepArchetype {
+    Ordinary,
+    /// Bolts from the player early and hard.
+    Skittish,
+    /// Slow, and content to wander off alone.
+    Stubborn,
+    /// Follows the heading of its neighbours closely.
+    Social,
+}
+
+impl SheepArchetype {
+    /// The flocking config for a sheep of this archetype.
+    pub fn adjust(self, config: &FlockingConfig) -> FlockingConfig {
+        let mut config = config.clone();
+        match self {
+            SheepArchetype::Ordinary => {}
+            SheepArchetype::Skittish => {
+                config.player_avoidance.strength *= 2.5;
+                config.player_avoidance.range *= 1.5;
+                config.panic.sensitivity *= 2.0;
+            }
+            SheepArchetype::Stubborn => {
+                config.max_speed *= 0.6;
+                config.sheep_coalescence.strength *= 0.3;
+                config.panic.sensitivity *= 0.5;
+            }
+            SheepArchetype::Social => {
+                config.sheep_alignment.strength *= 4.0;
+            }
+        }
+        config
+    }
+
+    pub fn tint(self) -> Color {
+        match self {
+            SheepArchetype::Ordinary => Color::ANTIQUE_WHITE,
+            SheepArchetype::Skittish => Color::hsl(50.0, 0.8, 0.75),
+            SheepArchetype::Stubborn => Color::hsl(25.0, 0.3, 0.45),
+            SheepArchetype::Social => Color::hsl(200.0, 0.5, 0.8),
+        }
+    }
+}
+
+/// The relative likelihood of each archetype among the sheep of a level.
+#[derive(Clone, Copy)]
+pub struct ArchetypeWeights {
+    pub ordinary: u32,
+    pub skittish: u32,
+    pub stubborn: u32,
+    pub social: u32,
+}
+
+impl ArchetypeWeights {
+    /// The mix for a round, starting with placid flocks and bringing in more
+    /// awkward sheep as the rounds go on.
+    pub fn for_round(round: usize) -> Self {
+        let round = round as u32;
+        Self {
+            ordinary: 6,
+            skittish: round.saturating_sub(1).min(4),
+            stubborn: round.saturating_sub(2).min(3),
+            social: 2,
+        }
+    }
+
+    pub fn choose(&self, rng: &mut fastrand::Rng) -> SheepArchetype {
+        let weighted = [
+            (SheepArchetype::Ordinary, self.ordinary),
+            (SheepArchetype::Skittish, self.skittish),
+            (SheepArchetype::Stubborn, self.stubborn),
+            (SheepArchetype::Social, self.social),
+        ];
+        let total = weighted.iter().map(|(_, weight)| weight).sum::<u32>();
+        if total == 0 {
+            return SheepArchetype::Ordinary;
+        }
+        let mut pick = rng.u32(0..total);
+        weighted
+            .iter()
+            .find(|(_, weight)| {
+                if pick < *weight {
+                    true
+                } else {
+                    pick -= weight;
+                    false
+                }
+            })
+            .map_or(SheepArchetype::Ordinary, |(archetype, _)| *archetype)
+    }
+}
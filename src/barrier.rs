@@ -19,42 +19,60 @@ impl Barrier {
         .clamp(0_f32, 1_f32);
         self.vertex_a + linear_position * b_to_a
     }
+
+    /// Corrects the motion from `start` to `end` so that it neither crosses
+    /// this barrier nor comes within `clearance` of it, sliding along the
+    /// barrier instead. Returns the corrected end point.
+    pub fn constrain_motion(
+        &self,
+        start: Vec2,
+        end: Vec2,
+        clearance: f32,
+    ) -> Vec2 {
+        let b_to_a = self.vertex_b - self.vertex_a;
+        let mut normal = b_to_a.perp().normalize_or_zero();
+        if (start - self.projected_point(start)).dot(normal) < 0_f32 {
+            normal = -normal;
+        }
+
+        let start_distance = (start - self.vertex_a).dot(normal);
+        let end_distance = (end - self.vertex_a).dot(normal);
+        if end_distance >= clearance || end_distance >= start_distance {
+            return end;
+        }
+
+        let contact_fraction = ((start_distance - clearance)
+            / (start_distance - end_distance))
+            .clamp(0_f32, 1_f32);
+        let contact = start + (end - start) * contact_fraction;
+        let linear_position =
+            (contact - self.vertex_a).dot(b_to_a) / b_to_a.length_squared();
+        let margin = clearance / b_to_a.length();
+        if linear_position < -margin || linear_position > 1_f32 + margin {
+            return end;
+        }
+
+        end + normal * (clearance - end_distance)
+    }
 }
 
 #[derive(Bundle)]
 pub struct BarrierBundle {
     barrier: Barrier,
     #[bundle]
-    mesh: PbrBundle,
+    transform: TransformBundle,
 }
 
 impl BarrierBundle {
-    fn new(
-        vertex_a: Vec2,
-        vertex_b: Vec2,
-        mesh_assets: &mut Assets<Mesh>,
-        standard_material_assets: &mut Assets<StandardMaterial>,
-    ) -> Self {
+    fn new(vertex_a: Vec2, vertex_b: Vec2) -> Self {
         Self {
             barrier: Barrier::new(vertex_a, vertex_b),
-            mesh: PbrBundle {
-                mesh: mesh_assets.add(Mesh::from(shape::Box {
-                    min_x: -0.1,
-                    max_x: 0.1,
-                    min_y: 0.0,
-                    max_y: 1.0,
-                    min_z: -(vertex_a - vertex_b).length(),
-                    max_z: 0.0,
-                })),
-                material: standard_material_assets
-                    .add(StandardMaterial::from(Color::hsl(26.0, 0.30, 0.35))),
-                transform: Transform::from_xyz(vertex_a.x, 0_f32, vertex_a.y)
-                    .looking_at(
-                        Vec3::new(vertex_b.x, 0_f32, vertex_b.y),
-                        Vec3::Y,
-                    ),
-                ..default()
-            },
+            transform: TransformBundle::from_transform(
+                Transform::from_xyz(vertex_a.x, 0_f32, vertex_a.y).looking_at(
+                    Vec3::new(vertex_b.x, 0_f32, vertex_b.y),
+                    Vec3::Y,
+                ),
+            ),
         }
     }
 
@@ -62,14 +80,38 @@ impl BarrierBundle {
         commands: &mut Commands,
         vertex_a: Vec2,
         vertex_b: Vec2,
-        mesh_assets: &mut ResMut<Assets<Mesh>>,
-        standard_material_assets: &mut ResMut<Assets<StandardMaterial>>,
-    ) {
-        commands.spawn(Self::new(
-            vertex_a,
-            vertex_b,
-            mesh_assets,
-            standard_material_assets,
+    ) -> Entity {
+        commands.spawn(Self::new(vertex_a, vertex_b)).id()
+    }
+}
+
+fn add_barrier_meshes(
+    mut commands: Commands,
+    barrier_query: Query<(Entity, &Barrier), Added<Barrier>>,
+    mut mesh_assets: ResMut<Assets<Mesh>>,
+    mut standard_material_assets: ResMut<Assets<StandardMaterial>>,
+) {
+    barrier_query.iter().for_each(|(entity, barrier)| {
+        commands.entity(entity).insert((
+            mesh_assets.add(Mesh::from(shape::Box {
+                min_x: -0.1,
+                max_x: 0.1,
+                min_y: 0.0,
+                max_y: 1.0,
+                min_z: -(barrier.vertex_a - barrier.vertex_b).length(),
+                max_z: 0.0,
+            })),
+            standard_material_assets
+                .add(StandardMaterial::from(Color::hsl(26.0, 0.30, 0.35))),
+            VisibilityBundle::default(),
         ));
+    });
+}
+
+pub struct BarrierRenderPlugin;
+
+impl Plugin for BarrierRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(add_barrier_meshes);
     }
 }
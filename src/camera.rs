@@ -1,17 +1,140 @@
+use std::f32::consts::FRAC_PI_2;
+
 use bevy::{core_pipeline::clear_color::ClearColorConfig, prelude::*};
+use leafwing_input_manager::{axislike::VirtualAxis, prelude::*};
+
+use crate::{
+    common::ground_position, pen::Pen, player::PlayerTag,
+    settings::ReservedKeys, sheep::SheepTag,
+};
+
+const MIN_PITCH: f32 = 0.15;
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.1;
+const MIN_DISTANCE: f32 = 20.0;
+const MAX_DISTANCE: f32 = 200.0;
+
+/// Radians of orbit per pixel of mouse motion.
+const MOUSE_ORBIT_SPEED: f32 = 0.005;
+/// Radians of orbit per second while an orbit or tilt key is held.
+const KEY_ORBIT_SPEED: f32 = 1.5;
+/// Logarithmic zoom per scroll line.
+const ZOOM_SPEED: f32 = 0.1;
+/// Rate at which the follow camera closes on its target, per second.
+const FOLLOW_DAMPING: f32 = 2.0;
+/// Extra room left around everything the follow camera keeps in view.
+const FOLLOW_MARGIN: f32 = 5.0;
 
 #[derive(Component)]
 pub struct MainCameraTag;
 
+#[derive(Actionlike, Copy, Clone, Debug)]
+enum CameraAction {
+    Grab,
+    Orbit,
+    Rotate,
+    Tilt,
+    Zoom,
+    ToggleFollow,
+}
+
+/// Whether the camera stays where it is put or follows the action.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Orbits a fixed focus under manual control only.
+    Static,
+    /// Tracks the player and pulls back to keep the player, pen and flock in
+    /// view.
+    Follow,
+}
+
+/// Positions the camera on a sphere about `focus`, at the given yaw about the
+/// vertical axis, pitch above the ground plane and distance.
+#[derive(Component)]
+pub struct OrbitCamera {
+    pub focus: Vec3,
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+}
+
+impl OrbitCamera {
+    fn from_offset(focus: Vec3, offset: Vec3) -> Self {
+        let distance = offset.length();
+        Self {
+            focus,
+            yaw: offset.x.atan2(offset.z),
+            pitch: (offset.y / distance).asin().clamp(MIN_PITCH, MAX_PITCH),
+            distance: distance.clamp(MIN_DISTANCE, MAX_DISTANCE),
+        }
+    }
+
+    fn orbit(&mut self, yaw: f32, pitch: f32) {
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(MIN_PITCH, MAX_PITCH);
+    }
+
+    /// Moves the focus and distance a `fraction` of the way towards the
+    /// given targets.
+    fn approach(&mut self, focus: Vec3, distance: f32, fraction: f32) {
+        self.focus = self.focus.lerp(focus, fraction);
+        self.distance = (self.distance + (distance - self.distance) * fraction)
+            .clamp(MIN_DISTANCE, MAX_DISTANCE);
+    }
+
+    fn zoom(&mut self, factor: f32) {
+        self.distance =
+            (self.distance * factor).clamp(MIN_DISTANCE, MAX_DISTANCE);
+    }
+
+    fn transform(&self) -> Transform {
+        let offset = Vec3::new(
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.cos(),
+        ) * self.distance;
+        Transform::from_translation(self.focus + offset)
+            .looking_at(self.focus, Vec3::Y)
+    }
+}
+
 #[derive(Bundle)]
 struct MainCameraBundle {
     tag: MainCameraTag,
+    orbit: OrbitCamera,
+    mode: CameraMode,
     #[bundle]
     camera_bundle: Camera3dBundle,
+    #[bundle]
+    input_manager: InputManagerBundle<CameraAction>,
 }
 
 impl MainCameraBundle {
-    fn new(transform: Transform) -> Self {
+    fn default_input_map() -> InputMap<CameraAction> {
+        let mut input_map = InputMap::default();
+
+        input_map.insert(MouseButton::Right, CameraAction::Grab);
+        input_map.insert(DualAxis::mouse_motion(), CameraAction::Orbit);
+        input_map.insert(
+            VirtualAxis {
+                negative: KeyCode::Q.into(),
+                positive: KeyCode::E.into(),
+            },
+            CameraAction::Rotate,
+        );
+        input_map.insert(
+            VirtualAxis {
+                negative: KeyCode::F.into(),
+                positive: KeyCode::R.into(),
+            },
+            CameraAction::Tilt,
+        );
+        input_map.insert(SingleAxis::mouse_wheel_y(), CameraAction::Zoom);
+        input_map.insert(KeyCode::C, CameraAction::ToggleFollow);
+
+        input_map
+    }
+
+    fn new(orbit: OrbitCamera) -> Self {
         Self {
             tag: MainCameraTag,
             camera_bundle: Camera3dBundle {
@@ -25,9 +148,15 @@ impl MainCameraBundle {
                     )),
                     ..default()
                 },
-                transform,
+                transform: orbit.transform(),
                 ..default()
             },
+            orbit,
+            mode: CameraMode::Static,
+            input_manager: InputManagerBundle {
+                action_state: ActionState::default(),
+                input_map: Self::default_input_map(),
+            },
         }
     }
 }
@@ -35,14 +164,160 @@ impl MainCameraBundle {
 pub struct MainCameraPlugin;
 
 fn startup(mut commands: Commands) {
-    commands.spawn(MainCameraBundle::new(
-        Transform::from_xyz(-50.0, 50.0, -100_f32)
-            .looking_at(Vec3::ZERO, Vec3::Y),
-    ));
+    commands.spawn(MainCameraBundle::new(OrbitCamera::from_offset(
+        Vec3::ZERO,
+        Vec3::new(-50.0, 50.0, -100_f32),
+    )));
+}
+
+/// The ratio by which a two finger pinch has spread since the last frame.
+fn pinch_factor(touches: &Touches) -> Option<f32> {
+    let mut pressed = touches.iter();
+    let (first, second) = (pressed.next()?, pressed.next()?);
+    let previous = first
+        .previous_position()
+        .distance(second.previous_position());
+    let current = first.position().distance(second.position());
+    (current > 0_f32).then(|| previous / current)
+}
+
+/// The distance at which a sphere of `radius` fits in view of `projection`.
+fn framing_distance(projection: &Projection, radius: f32) -> f32 {
+    match projection {
+        Projection::Perspective(perspective) => {
+            let half_fov = (perspective.fov / 2_f32).min(
+                ((perspective.fov / 2_f32).tan() * perspective.aspect_ratio)
+                    .atan(),
+            );
+            radius / half_fov.sin()
+        }
+        Projection::Orthographic(_) => radius,
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn follow_targets(
+    mut camera_query: Query<
+        (
+            &mut OrbitCamera,
+            &mut CameraMode,
+            &Projection,
+            &ActionState<CameraAction>,
+        ),
+        With<MainCameraTag>,
+    >,
+    player_query: Query<&Transform, (With<PlayerTag>, Without<MainCameraTag>)>,
+    sheep_query: Query<&Transform, (With<SheepTag>, Without<MainCameraTag>)>,
+    pen_query: Query<&Pen>,
+    time: Res<Time>,
+) {
+    camera_query.iter_mut().for_each(
+        |(mut orbit, mut mode, projection, action)| {
+            if action.just_pressed(CameraAction::ToggleFollow) {
+                *mode = match *mode {
+                    CameraMode::Static => CameraMode::Follow,
+                    CameraMode::Follow => CameraMode::Static,
+                };
+            }
+            if *mode != CameraMode::Follow {
+                return;
+            }
+
+            let points = player_query
+                .iter()
+                .chain(sheep_query.iter())
+                .map(ground_position)
+                .chain(
+                    pen_query
+                        .iter()
+                        .flat_map(|pen| pen.vertices().iter().copied()),
+                )
+                .collect::<Vec<_>>();
+            if points.is_empty() {
+                return;
+            }
+            let (min, max) = points.iter().fold(
+                (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+                |(min, max), &point| (min.min(point), max.max(point)),
+            );
+            let centre = (min + max) / 2_f32;
+            let radius = points
+                .iter()
+                .map(|point| point.distance(centre))
+                .fold(0_f32, f32::max)
+                + FOLLOW_MARGIN;
+
+            orbit.approach(
+                Vec3::new(centre.x, 0_f32, centre.y),
+                framing_distance(projection, radius),
+                1_f32 - (-FOLLOW_DAMPING * time.delta_seconds()).exp(),
+            );
+        },
+    )
+}
+
+#[derive(SystemLabel)]
+struct ControlCameraLabel;
+
+fn control_camera(
+    mut camera_query: Query<
+        (
+            &mut OrbitCamera,
+            &mut Transform,
+            &CameraMode,
+            &ActionState<CameraAction>,
+        ),
+        With<MainCameraTag>,
+    >,
+    touches: Res<Touches>,
+    time: Res<Time>,
+) {
+    camera_query.iter_mut().for_each(
+        |(mut orbit, mut transform, mode, action)| {
+            if action.pressed(CameraAction::Grab) {
+                if let Some(motion) = action.axis_pair(CameraAction::Orbit) {
+                    orbit.orbit(
+                        -motion.x() * MOUSE_ORBIT_SPEED,
+                        motion.y() * MOUSE_ORBIT_SPEED,
+                    );
+                }
+            }
+            orbit.orbit(
+                action.value(CameraAction::Rotate)
+                    * KEY_ORBIT_SPEED
+                    * time.delta_seconds(),
+                action.value(CameraAction::Tilt)
+                    * KEY_ORBIT_SPEED
+                    * time.delta_seconds(),
+            );
+            if *mode == CameraMode::Static {
+                orbit.zoom(
+                    (-action.value(CameraAction::Zoom).clamp(-5_f32, 5_f32)
+                        * ZOOM_SPEED)
+                        .exp(),
+                );
+                if let Some(factor) = pinch_factor(&touches) {
+                    orbit.zoom(factor);
+                }
+            }
+
+            *transform = orbit.transform();
+        },
+    )
+}
+
+/// Keeps rebound actions off the camera's keys.
+fn reserve_camera_keys(mut reserved: ResMut<ReservedKeys>) {
+    reserved.reserve("camera", &MainCameraBundle::default_input_map());
 }
 
 impl Plugin for MainCameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(startup);
+        app.add_plugin(InputManagerPlugin::<CameraAction>::default())
+            .init_resource::<ReservedKeys>()
+            .add_startup_system(startup)
+            .add_startup_system(reserve_camera_keys)
+            .add_system(follow_targets.before(ControlCameraLabel))
+            .add_system(control_camera.label(ControlCameraLabel));
     }
 }
@@ -1,5 +1,52 @@
 use bevy::prelude::*;
 
+/// Marks entities that belong to a single round and are despawned when it
+/// ends.
+#[derive(Component)]
+pub struct RoundScoped;
+
+/// The seed from which every level layout is generated, so that a layout can
+/// be reproduced by reusing its seed.
+#[derive(Resource, Clone, Copy)]
+pub struct LevelSeed(pub u64);
+
+impl Default for LevelSeed {
+    fn default() -> Self {
+        Self(fastrand::u64(..))
+    }
+}
+
+impl LevelSeed {
+    /// The random number generator for the given round, with round zero
+    /// being the field itself. The seed and round are mixed rather than
+    /// added, so that neighbouring seeds do not share levels a round apart.
+    pub fn rng(&self, round: usize) -> fastrand::Rng {
+        fastrand::Rng::with_seed(splitmix64(self.0 ^ splitmix64(round as u64)))
+    }
+}
+
+/// One step of the SplitMix64 generator, scrambling every bit of `value`.
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Marks entities which frighten sheep by rushing at them.
+#[derive(Component)]
+pub struct Threat;
+
+/// Scales the range at which sheep shy away from an entity, for entities
+/// which are at times more alarming than others.
+#[derive(Component)]
+pub struct Presence(pub f32);
+
+/// Where an entity stands on the ground plane.
+pub fn ground_position(transform: &Transform) -> Vec2 {
+    Vec2::new(transform.translation.x, transform.translation.z)
+}
+
 #[derive(Component)]
 pub struct MaxSpeed(pub f32);
 
@@ -8,3 +55,25 @@ impl MaxSpeed {
         Self(speed)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_of_neighbouring_seeds_differ() {
+        (0..100_u64).for_each(|seed| {
+            (0..10_usize).for_each(|round| {
+                assert_ne!(
+                    LevelSeed(seed).rng(round + 1).u64(..),
+                    LevelSeed(seed + 1).rng(round).u64(..)
+                );
+            })
+        })
+    }
+
+    #[test]
+    fn same_seed_and_round_repeat() {
+        assert_eq!(LevelSeed(42).rng(3).u64(..), LevelSeed(42).rng(3).u64(..));
+    }
+}
@@ -0,0 +1,278 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::barrier::Barrier;
+use crate::common::ground_position;
+use crate::pen::Pen;
+use crate::player::MovePlayerLabel;
+use crate::settings::{Bindings, BindingsPlugin, Rebindable};
+use crate::sheep::SheepTag;
+use crate::simulation::{
+    step_seconds, Interpolated, SIMULATION_SUBSTAGE, SIMULATION_TIMESTEP,
+};
+
+/// The radius of the dog's body, used for collisions with barriers.
+const DOG_RADIUS: f32 = 0.4;
+
+const RUN_SPEED: f32 = 9.0;
+const WALK_SPEED: f32 = 3.0;
+
+/// How far outside the flock the dog runs when circling it.
+const CIRCLING_MARGIN: f32 = 8.0;
+/// How close to the edge of the flock the dog walks up.
+const WALK_UP_MARGIN: f32 = 4.0;
+/// How near, in radians, the dog must come to the balance point to stop
+/// circling and hold there.
+const BALANCE_TOLERANCE: f32 = 0.15;
+
+#[derive(Component)]
+pub struct DogTag;
+
+/// The herding commands the shepherd gives the dog.
+#[derive(
+    Actionlike,
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+pub enum DogAction {
+    /// Circle the flock clockwise.
+    ComeBy,
+    /// Circle the flock anticlockwise.
+    Away,
+    /// Walk straight at the flock.
+    WalkUp,
+    /// Stop where it is.
+    LieDown,
+}
+
+impl Rebindable for DogAction {
+    const STORAGE_KEY: &'static str = "dog_bindings";
+
+    fn default_input_map() -> InputMap<Self> {
+        let mut input_map = InputMap::default();
+
+        input_map.insert(KeyCode::Key1, DogAction::ComeBy);
+        input_map.insert(KeyCode::Key2, DogAction::Away);
+        input_map.insert(KeyCode::Key3, DogAction::WalkUp);
+        input_map.insert(KeyCode::Key4, DogAction::LieDown);
+
+        input_map.insert(GamepadButtonType::East, DogAction::ComeBy);
+        input_map.insert(GamepadButtonType::West, DogAction::Away);
+        input_map.insert(GamepadButtonType::North, DogAction::WalkUp);
+        input_map.insert(GamepadButtonType::South, DogAction::LieDown);
+
+        input_map
+    }
+}
+
+/// The command the dog is carrying out, held until another is given.
+#[derive(Component)]
+struct Dog {
+    command: DogAction,
+}
+
+#[derive(Bundle)]
+pub struct DogBundle {
+    tag: DogTag,
+    dog: Dog,
+    #[bundle]
+    transform: TransformBundle,
+    interpolated: Interpolated,
+    #[bundle]
+    input_manager: InputManagerBundle<DogAction>,
+}
+
+impl DogBundle {
+    fn new(position: Vec2, input_map: InputMap<DogAction>) -> Self {
+        let transform = Transform::from_xyz(position.x, 0_f32, position.y);
+        Self {
+            tag: DogTag,
+            dog: Dog {
+                command: DogAction::LieDown,
+            },
+            transform: TransformBundle::from_transform(transform),
+            interpolated: Interpolated::new(transform),
+            input_manager: InputManagerBundle {
+                action_state: ActionState::default(),
+                input_map,
+            },
+        }
+    }
+
+    pub fn spawn(
+        commands: &mut Commands,
+        position: Vec2,
+        bindings: &Bindings<DogAction>,
+    ) {
+        commands.spawn(DogBundle::new(position, bindings.0.clone()));
+    }
+}
+
+fn add_dog_meshes(
+    mut commands: Commands,
+    dog_query: Query<Entity, Added<DogTag>>,
+    mut mesh_assets: ResMut<Assets<Mesh>>,
+    mut standard_material_assets: ResMut<Assets<StandardMaterial>>,
+) {
+    dog_query.iter().for_each(|entity| {
+        commands.entity(entity).insert((
+            mesh_assets.add(Mesh::from(shape::Box {
+                min_x: -0.5,
+                max_x: 0.5,
+                min_y: 0.0,
+                max_y: 0.5,
+                min_z: -0.2,
+                max_z: 0.2,
+            })),
+            standard_material_assets
+                .add(StandardMaterial::from(Color::hsl(0.0, 0.0, 0.1))),
+            VisibilityBundle::default(),
+        ));
+    });
+}
+
+fn command_dog(mut dog_query: Query<(&mut Dog, &ActionState<DogAction>)>) {
+    dog_query.iter_mut().for_each(|(mut dog, action)| {
+        if let Some(command) = action.get_just_pressed().last() {
+            dog.command = *command;
+        }
+    })
+}
+
+/// The velocity carrying the dog around the flock in the given direction,
+/// keeping it `radius` from the centre, until it reaches the balance point
+/// where it holds the flock between itself and the pen.
+fn circling_velocity(
+    position: Vec2,
+    centre: Vec2,
+    radius: f32,
+    balance: Vec2,
+    clockwise: bool,
+) -> Vec2 {
+    let offset = position - centre;
+    let outward = offset.try_normalize().unwrap_or(balance);
+    let remaining = if clockwise {
+        outward.angle_between(balance)
+    } else {
+        balance.angle_between(outward)
+    }
+    .rem_euclid(TAU);
+    // Overshooting a little should not send the dog round again.
+    if remaining.min(TAU - remaining) < BALANCE_TOLERANCE {
+        let hold = centre + balance * radius - position;
+        return hold.clamp_length_max(RUN_SPEED);
+    }
+
+    let tangent = if clockwise {
+        Vec2::new(-outward.y, outward.x)
+    } else {
+        Vec2::new(outward.y, -outward.x)
+    };
+    let radial = radius - offset.length();
+    (tangent * RUN_SPEED + outward * radial).clamp_length_max(RUN_SPEED)
+}
+
+#[derive(SystemLabel)]
+pub struct MoveDogLabel;
+
+fn move_dog(
+    mut dog_query: Query<(&Dog, &mut Transform), Without<SheepTag>>,
+    sheep_query: Query<&Transform, With<SheepTag>>,
+    pen_query: Query<&Pen>,
+    barrier_query: Query<&Barrier>,
+    timesteps: Res<FixedTimesteps>,
+) {
+    let delta_seconds = step_seconds(&timesteps);
+    let sheep_count = sheep_query.iter().count();
+    if sheep_count == 0 {
+        return;
+    }
+    let centre = sheep_query.iter().map(ground_position).sum::<Vec2>()
+        / sheep_count as f32;
+    let flock_radius = sheep_query
+        .iter()
+        .map(|transform| ground_position(transform).distance(centre))
+        .fold(0_f32, f32::max);
+    // The side of the flock facing away from the pen, from which the dog
+    // drives the sheep towards it.
+    let balance = pen_query
+        .get_single()
+        .ok()
+        .and_then(|pen| (centre - pen.centre()).try_normalize())
+        .unwrap_or(Vec2::Y);
+
+    dog_query.iter_mut().for_each(|(dog, mut transform)| {
+        let position = ground_position(&transform);
+        let velocity = match dog.command {
+            DogAction::ComeBy | DogAction::Away => circling_velocity(
+                position,
+                centre,
+                flock_radius + CIRCLING_MARGIN,
+                balance,
+                dog.command == DogAction::ComeBy,
+            ),
+            DogAction::WalkUp => {
+                let offset = centre - position;
+                let gap = offset.length() - flock_radius - WALK_UP_MARGIN;
+                offset.normalize_or_zero() * gap.clamp(0_f32, WALK_SPEED)
+            }
+            DogAction::LieDown => Vec2::ZERO,
+        };
+
+        let end = barrier_query
+            .iter()
+            .fold(position + velocity * delta_seconds, |end, barrier| {
+                barrier.constrain_motion(position, end, DOG_RADIUS)
+            });
+        transform.translation.x = end.x;
+        transform.translation.z = end.y;
+        if velocity != Vec2::ZERO {
+            transform.rotation =
+                Quat::from_rotation_y(velocity.angle_between(Vec2::X));
+        }
+    })
+}
+
+pub struct DogPlugin;
+
+impl Plugin for DogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Bindings<DogAction>>()
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                move_dog.label(MoveDogLabel).after(MovePlayerLabel),
+            );
+    }
+}
+
+/// Takes the dog's commands from the shepherd's keyboard or gamepad.
+pub struct DogInputPlugin;
+
+impl Plugin for DogInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InputManagerPlugin::<DogAction>::default())
+            .add_plugin(BindingsPlugin::<DogAction>::default())
+            .add_system(command_dog);
+    }
+}
+
+pub struct DogRenderPlugin;
+
+impl Plugin for DogRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(add_dog_meshes);
+    }
+}
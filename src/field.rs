@@ -1,4 +1,4 @@
-use bevy::prelude::{Assets, Commands, Mesh, ResMut, StandardMaterial, Vec2};
+use bevy::prelude::{Commands, Vec2};
 
 use crate::barrier::BarrierBundle;
 
@@ -6,50 +6,38 @@ pub struct Field {
     bounds: [Vec2; 4],
 }
 
-impl Default for Field {
-    fn default() -> Self {
+impl Field {
+    fn new(rng: &mut fastrand::Rng) -> Self {
         Self {
             bounds: [
                 Vec2::new(
-                    45_f32 + fastrand::f32() * 10_f32,
-                    45_f32 + fastrand::f32() * 10_f32,
+                    45_f32 + rng.f32() * 10_f32,
+                    45_f32 + rng.f32() * 10_f32,
                 ),
                 Vec2::new(
-                    45_f32 + fastrand::f32() * 10_f32,
-                    -45_f32 - fastrand::f32() * 10_f32,
+                    45_f32 + rng.f32() * 10_f32,
+                    -45_f32 - rng.f32() * 10_f32,
                 ),
                 Vec2::new(
-                    -45_f32 - fastrand::f32() * 10_f32,
-                    -45_f32 - fastrand::f32() * 10_f32,
+                    -45_f32 - rng.f32() * 10_f32,
+                    -45_f32 - rng.f32() * 10_f32,
                 ),
                 Vec2::new(
-                    -45_f32 - fastrand::f32() * 10_f32,
-                    45_f32 + fastrand::f32() * 10_f32,
+                    -45_f32 - rng.f32() * 10_f32,
+                    45_f32 + rng.f32() * 10_f32,
                 ),
             ],
         }
     }
-}
 
-impl Field {
-    pub fn spawn(
-        commands: &mut Commands,
-        mesh_assets: &mut ResMut<Assets<Mesh>>,
-        standard_material_assets: &mut ResMut<Assets<StandardMaterial>>,
-    ) {
-        let field = Self::default();
+    pub fn spawn(commands: &mut Commands, rng: &mut fastrand::Rng) {
+        let field = Self::new(rng);
         field
             .bounds
             .iter()
             .zip(field.bounds.iter().cycle().skip(1))
             .for_each(|(&vertex_a, &vertex_b)| {
-                BarrierBundle::spawn(
-                    commands,
-                    vertex_a,
-                    vertex_b,
-                    mesh_assets,
-                    standard_material_assets,
-                )
+                BarrierBundle::spawn(commands, vertex_a, vertex_b);
             });
     }
 }
@@ -0,0 +1,177 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+const FLOCKING_CONFIG_PATH: &str = "flocking.ron";
+
+#[derive(Clone, Copy, Deserialize)]
+pub struct InfluenceConfig {
+    pub strength: f32,
+    pub range: f32,
+}
+
+impl InfluenceConfig {
+    const fn new(strength: f32, range: f32) -> Self {
+        Self { strength, range }
+    }
+}
+
+/// How sheep amble about when nothing else is moving them.
+#[derive(Clone, Copy, Deserialize)]
+pub struct WanderConfig {
+    pub strength: f32,
+    /// The mean time, in seconds, between changes of heading or grazing.
+    pub interval: f32,
+    /// The chance that a sheep stops to graze instead of picking a new
+    /// heading.
+    pub graze_chance: f32,
+}
+
+/// How sheep take fright at threats, and at each other.
+#[derive(Clone, Copy, Deserialize)]
+pub struct PanicConfig {
+    /// How close a threat must come before it frightens a sheep.
+    pub range: f32,
+    /// Panic gained per second, per unit of a threat's closing speed, when
+    /// the threat is right beside a sheep.
+    pub sensitivity: f32,
+    /// How quickly a sheep's panic rises towards its most panicked
+    /// neighbour's, per second.
+    pub contagion: f32,
+    /// Panic lost per second.
+    pub calm_rate: f32,
+    /// The fraction by which full panic raises a sheep's top speed.
+    pub speed_boost: f32,
+    /// The factor by which full panic scales a sheep's coalescence.
+    pub coalescence: f32,
+}
+
+/// Tunable flocking parameters, loaded from `assets/flocking.ron`.
+#[derive(Clone, Deserialize, TypeUuid)]
+#[uuid = "256a8fe6-0863-4035-8083-a0a974f0c493"]
+pub struct FlockingConfig {
+    pub max_speed: f32,
+    pub drag: f32,
+    pub player_avoidance: InfluenceConfig,
+    pub wolf_avoidance: InfluenceConfig,
+    pub dog_avoidance: InfluenceConfig,
+    pub barrier_avoidance: InfluenceConfig,
+    pub sheep_avoidance: InfluenceConfig,
+    pub sheep_coalescence: InfluenceConfig,
+    pub sheep_alignment: InfluenceConfig,
+    pub wander: WanderConfig,
+    pub panic: PanicConfig,
+}
+
+impl Default for FlockingConfig {
+    fn default() -> Self {
+        Self {
+            max_speed: 5.0,
+            drag: 0.8,
+            player_avoidance: InfluenceConfig::new(100.0, 10_f32),
+            wolf_avoidance: InfluenceConfig::new(150.0, 15_f32),
+            dog_avoidance: InfluenceConfig::new(100.0, 10_f32),
+            barrier_avoidance: InfluenceConfig::new(100.0, 5_f32),
+            sheep_avoidance: InfluenceConfig::new(10.0, 10_f32),
+            sheep_coalescence: InfluenceConfig::new(5.0, 10_f32),
+            sheep_alignment: InfluenceConfig::new(1.0, 10_f32),
+            wander: WanderConfig {
+                strength: 1.5,
+                interval: 4.0,
+                graze_chance: 0.4,
+            },
+            panic: PanicConfig {
+                range: 12.0,
+                sensitivity: 0.15,
+                contagion: 1.5,
+                calm_rate: 0.2,
+                speed_boost: 1.0,
+                coalescence: 0.2,
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+struct FlockingConfigLoader;
+
+impl AssetLoader for FlockingConfigLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let config = ron::de::from_bytes::<FlockingConfig>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(config));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+/// The currently active flocking parameters, replaced whenever the config
+/// asset is loaded or modified on disk.
+#[derive(Resource, Default)]
+pub struct ActiveFlockingConfig {
+    handle: Handle<FlockingConfig>,
+    pub config: FlockingConfig,
+}
+
+fn load_flocking_config(
+    mut active_config: ResMut<ActiveFlockingConfig>,
+    asset_server: Res<AssetServer>,
+) {
+    active_config.handle = asset_server.load(FLOCKING_CONFIG_PATH);
+}
+
+fn update_flocking_config(
+    mut active_config: ResMut<ActiveFlockingConfig>,
+    mut asset_events: EventReader<AssetEvent<FlockingConfig>>,
+    config_assets: Res<Assets<FlockingConfig>>,
+) {
+    asset_events.iter().for_each(|event| match event {
+        AssetEvent::Created { handle } | AssetEvent::Modified { handle }
+            if *handle == active_config.handle =>
+        {
+            if let Some(config) = config_assets.get(handle) {
+                active_config.config = config.clone();
+            }
+        }
+        _ => {}
+    });
+}
+
+/// Labels the simulation system that hands the active parameters to the
+/// sheep, so that loaders can run before it.
+#[derive(SystemLabel)]
+pub struct FlockingConfigLabel;
+
+/// Provides the default flocking parameters to the simulation.
+pub struct FlockingPlugin;
+
+impl Plugin for FlockingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveFlockingConfig>();
+    }
+}
+
+/// Loads the flocking parameters from the config asset, hot reloading them
+/// where the asset server watches for changes.
+pub struct FlockingConfigPlugin;
+
+impl Plugin for FlockingConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<FlockingConfig>()
+            .init_asset_loader::<FlockingConfigLoader>()
+            .add_startup_system(load_flocking_config)
+            .add_system(update_flocking_config.before(FlockingConfigLabel));
+    }
+}
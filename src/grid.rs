@@ -0,0 +1,74 @@
+use bevy::{prelude::*, utils::HashMap};
+
+#[derive(Clone, Copy)]
+pub struct GridEntry {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub panic: f32,
+}
+
+/// A uniform grid over the ground plane, bucketing entities by the cell
+/// their position falls in so that neighbour queries only visit nearby cells.
+#[derive(Resource)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<GridEntry>>,
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self::new(10_f32)
+    }
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+        }
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.values_mut().for_each(Vec::clear);
+    }
+
+    pub fn insert(
+        &mut self,
+        entity: Entity,
+        position: Vec2,
+        velocity: Vec2,
+        panic: f32,
+    ) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push(GridEntry {
+            entity,
+            position,
+            velocity,
+            panic,
+        });
+    }
+
+    /// Entries strictly within `range` of `position`.
+    pub fn neighbours(
+        &self,
+        position: Vec2,
+        range: f32,
+    ) -> impl Iterator<Item = &GridEntry> {
+        let min = self.cell(position - Vec2::splat(range));
+        let max = self.cell(position + Vec2::splat(range));
+        let range_squared = range.powi(2);
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(move |entry| {
+                (entry.position - position).length_squared() < range_squared
+            })
+    }
+}
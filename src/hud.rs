@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+
+use crate::common::{ground_position, LevelSeed};
+use crate::pen::Pen;
+use crate::player::{Stamina, STAMINA_MAX};
+use crate::round::{RoundClock, RoundManager};
+use crate::sheep::SheepTag;
+
+/// The window height at which the HUD is drawn at its natural size.
+const REFERENCE_HEIGHT: f32 = 720.0;
+
+const ROUND_SECTION: usize = 1;
+const PENNED_SECTION: usize = 3;
+const CLOCK_SECTION: usize = 5;
+const SEED_SECTION: usize = 7;
+const STAMINA_SECTION: usize = 9;
+
+#[derive(Component)]
+struct HudText;
+
+fn spawn_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/FiraSans-Regular.ttf");
+    let label_style = TextStyle {
+        font: font.clone(),
+        font_size: 24.0,
+        color: Color::hsl(0.0, 0.0, 0.8),
+    };
+    let value_style = TextStyle {
+        font,
+        font_size: 24.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    ..default()
+                },
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.4).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_sections([
+                    TextSection::new("Round ", label_style.clone()),
+                    TextSection::from_style(value_style.clone()),
+                    TextSection::new("\nPenned ", label_style.clone()),
+                    TextSection::from_style(value_style.clone()),
+                    TextSection::new("\nTime ", label_style.clone()),
+                    TextSection::from_style(value_style.clone()),
+                    TextSection::new("\nSeed ", label_style.clone()),
+                    TextSection::from_style(value_style.clone()),
+                    TextSection::new("\nStamina ", label_style),
+                    TextSection::from_style(value_style),
+                ]),
+                HudText,
+            ));
+        });
+}
+
+fn update_hud(
+    mut text_query: Query<&mut Text, With<HudText>>,
+    round_manager_query: Query<&RoundManager>,
+    pen_query: Query<&Pen>,
+    sheep_query: Query<&Transform, With<SheepTag>>,
+    stamina_query: Query<&Stamina>,
+    round_clock: Res<RoundClock>,
+    seed: Res<LevelSeed>,
+) {
+    let total = sheep_query.iter().count();
+    let penned = pen_query.get_single().map_or(0, |pen| {
+        sheep_query
+            .iter()
+            .filter(|transform| pen.contains(ground_position(transform)))
+            .count()
+    });
+    let seconds = round_clock.0.elapsed().as_secs();
+
+    text_query.iter_mut().for_each(|mut text| {
+        if let Ok(round_manager) = round_manager_query.get_single() {
+            text.sections[ROUND_SECTION].value =
+                round_manager.round().to_string();
+        }
+        text.sections[PENNED_SECTION].value = format!("{penned} / {total}");
+        text.sections[CLOCK_SECTION].value =
+            format!("{:02}:{:02}", seconds / 60, seconds % 60);
+        text.sections[SEED_SECTION].value = seed.0.to_string();
+        if let Ok(stamina) = stamina_query.get_single() {
+            text.sections[STAMINA_SECTION].value =
+                format!("{:.0}%", stamina.remaining / STAMINA_MAX * 100_f32);
+        }
+    })
+}
+
+/// Scales the UI with the window so the HUD stays legible on both small and
+/// large canvases.
+fn scale_hud(windows: Res<Windows>, mut ui_scale: ResMut<UiScale>) {
+    if let Some(window) = windows.get_primary() {
+        let scale = (window.height() / REFERENCE_HEIGHT).clamp(0.75, 2.0);
+        if (ui_scale.scale - scale as f64).abs() > f64::EPSILON {
+            ui_scale.scale = scale as f64;
+        }
+    }
+}
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_hud)
+            .add_system(update_hud)
+            .add_system(scale_hud);
+    }
+}
@@ -1,165 +1,116 @@
+mod archetype;
 mod barrier;
 mod camera;
 mod common;
+mod dog;
 mod field;
+mod flocking;
+mod grid;
+mod hud;
 mod pen;
 mod player;
+mod pointer;
+mod round;
+mod settings;
 mod sheep;
+mod simulation;
+mod steering;
+mod storage;
 mod terrain;
+mod wolf;
 
-use bevy::prelude::*;
+use barrier::BarrierRenderPlugin;
+use bevy::{app::PluginGroupBuilder, prelude::*};
 use camera::MainCameraPlugin;
-use field::Field;
-use iyes_loopless::prelude::*;
-use pen::{Pen, PenBundle};
-use player::{PlayerBundle, PlayerPlugin};
-use sheep::{SheepBundle, SheepPlugin, SheepTag};
+use dog::{DogInputPlugin, DogPlugin, DogRenderPlugin};
+use flocking::{FlockingConfigPlugin, FlockingPlugin};
+use hud::HudPlugin;
+use pen::PenRenderPlugin;
+use player::{PlayerInputPlugin, PlayerPlugin, PlayerRenderPlugin};
+use pointer::PointerInputPlugin;
+use round::RoundPlugin;
+use settings::SettingsPlugin;
+use sheep::{SheepPlugin, SheepRenderPlugin};
+use simulation::{InterpolationPlugin, SimulationPlugin};
 use terrain::TerrainPlugin;
+use wolf::{WolfPlugin, WolfRenderPlugin};
 
-pub const LAUNCHER_TITLE: &str = "Flock! Combine the herd.";
-
-#[derive(Clone, PartialEq, Eq, Debug, Hash)]
-enum GameState {
-    Playing,
-    Success,
-}
+pub use common::LevelSeed;
+pub use player::PlayerTag;
+pub use round::GameState;
+pub use sheep::SheepTag;
 
-pub fn app() -> App {
-    let mut app = App::new();
-    app.add_plugins(DefaultPlugins.set(WindowPlugin {
-        window: WindowDescriptor {
-            title: LAUNCHER_TITLE.to_string(),
-            canvas: Some("#bevy".to_string()),
-            fit_canvas_to_parent: true,
-            ..Default::default()
-        },
-        ..default()
-    }))
-    .add_plugin(MainCameraPlugin)
-    .add_plugin(PlayerPlugin)
-    .add_plugin(SheepPlugin)
-    .add_plugin(TerrainPlugin)
-    .add_loopless_state(GameState::Playing)
-    .add_enter_system(GameState::Playing, start_round)
-    .add_system(check_win.run_in_state(GameState::Playing))
-    .add_startup_system(setup);
-    app
-}
-
-#[derive(Component)]
-struct RoundManager(usize);
-
-impl RoundManager {
-    fn new() -> Self {
-        Self(0)
-    }
-
-    fn next_level(&mut self) {
-        self.0 += 1;
-    }
+pub const LAUNCHER_TITLE: &str = "Flock! Combine the herd.";
 
-    fn get_cluster_sizes(&self) -> Vec<usize> {
-        (0..self.0)
-            .map(|_| fastrand::usize(1..self.0 + 1))
-            .collect()
+/// The game simulation: sheep, player movement, pen, field and round logic.
+/// Runs on `MinimalPlugins` without a window or renderer.
+pub struct FlockSimulationPlugins;
+
+impl PluginGroup for FlockSimulationPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(SimulationPlugin)
+            .add(FlockingPlugin)
+            .add(PlayerPlugin)
+            .add(DogPlugin)
+            .add(SheepPlugin)
+            .add(WolfPlugin)
+            .add(RoundPlugin)
     }
 }
 
-fn spawn_cluster(
-    commands: &mut Commands,
-    mesh_assets: &mut ResMut<Assets<Mesh>>,
-    standard_material_assets: &mut ResMut<Assets<StandardMaterial>>,
-    count: usize,
-) {
-    let cluster_position = Vec2::new(
-        fastrand::f32() * 80_f32 - 40_f32,
-        fastrand::f32() * 80_f32 - 40_f32,
-    );
-    (0..count).for_each(|_| {
-        SheepBundle::spawn(
-            commands,
-            mesh_assets,
-            standard_material_assets,
-            cluster_position
-                + Vec2::new(
-                    fastrand::f32() * 10_f32 - 5_f32,
-                    fastrand::f32() * 10_f32 - 5_f32,
-                ),
-        );
-    });
-}
-
-fn check_win(
-    pen_query: Query<&Pen>,
-    sheep_query: Query<&Transform, With<SheepTag>>,
-    mut commands: Commands,
-) {
-    if let Ok(pen) = pen_query.get_single() {
-        if sheep_query.iter().all(|transform| {
-            pen.contains(Vec2::new(
-                transform.translation.x,
-                transform.translation.z,
-            ))
-        }) {
-            commands.insert_resource(NextState(GameState::Success))
-        }
+/// The windowed layer on top of [`FlockSimulationPlugins`]: meshes, camera,
+/// lighting, HUD, asset loading and device input. Requires `DefaultPlugins`.
+pub struct FlockClientPlugins;
+
+impl PluginGroup for FlockClientPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(FlockingConfigPlugin)
+            .add(InterpolationPlugin)
+            .add(MainCameraPlugin)
+            .add(TerrainPlugin)
+            .add(PlayerInputPlugin)
+            .add(PointerInputPlugin)
+            .add(DogInputPlugin)
+            .add(PlayerRenderPlugin)
+            .add(DogRenderPlugin)
+            .add(SheepRenderPlugin)
+            .add(WolfRenderPlugin)
+            .add(BarrierRenderPlugin)
+            .add(PenRenderPlugin)
+            .add(HudPlugin)
+            .add(SettingsPlugin)
     }
 }
 
-fn setup(
-    mut commands: Commands,
-    mut mesh_assets: ResMut<Assets<Mesh>>,
-    mut standard_material_assets: ResMut<Assets<StandardMaterial>>,
-) {
-    commands.spawn(RoundManager::new());
-
-    commands.spawn(DirectionalLightBundle {
-        directional_light: DirectionalLight {
-            color: Color::WHITE,
-            illuminance: 50000_f32,
-            shadows_enabled: true,
-            ..default()
-        },
-        transform: Transform::from_xyz(-100_f32, 50_f32, -50_f32)
-            .looking_at(Vec3::ZERO, Vec3::Y),
-        ..default()
-    });
-
-    Field::spawn(
-        &mut commands,
-        &mut mesh_assets,
-        &mut standard_material_assets,
-    );
-
-    PlayerBundle::spawn(
-        &mut commands,
-        &mut mesh_assets,
-        &mut standard_material_assets,
-        Vec2::new(0_f32, 0_f32),
-    );
+pub fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                window: WindowDescriptor {
+                    title: LAUNCHER_TITLE.to_string(),
+                    canvas: Some("#bevy".to_string()),
+                    fit_canvas_to_parent: true,
+                    ..Default::default()
+                },
+                ..default()
+            })
+            .set(AssetPlugin {
+                watch_for_changes: cfg!(not(target_arch = "wasm32")),
+                ..default()
+            }),
+    )
+    .add_plugins(FlockSimulationPlugins)
+    .add_plugins(FlockClientPlugins);
+    app
 }
 
-fn start_round(
-    mut commands: Commands,
-    mut mesh_assets: ResMut<Assets<Mesh>>,
-    mut standard_material_assets: ResMut<Assets<StandardMaterial>>,
-    mut round_manager_query: Query<&mut RoundManager>,
-) {
-    let mut round_manager = round_manager_query.single_mut();
-    round_manager.next_level();
-
-    PenBundle::spawn(
-        &mut commands,
-        &mut mesh_assets,
-        &mut standard_material_assets,
-    );
-
-    round_manager.get_cluster_sizes().iter().for_each(|&count| {
-        spawn_cluster(
-            &mut commands,
-            &mut mesh_assets,
-            &mut standard_material_assets,
-            count,
-        )
-    });
+/// A windowless app running only the simulation, for tests and tooling.
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(FlockSimulationPlugins);
+    app
 }
@@ -1,120 +1,252 @@
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
 
 use crate::barrier::BarrierBundle;
+use crate::common::RoundScoped;
+
+/// Tolerance within which a point is considered to lie on a pen edge.
+const EDGE_EPSILON: f32 = 1e-4;
 
 #[derive(Component)]
 pub struct Pen {
-    centre: Vec2,
-    width: f32,
-    height: f32,
-    angle: f32,
+    vertices: Vec<Vec2>,
 }
 
 impl Pen {
-    fn new(centre: Vec2, width: f32, height: f32, angle: f32) -> Self {
-        Self {
-            centre,
-            width,
-            height,
-            angle,
-        }
+    fn new(vertices: Vec<Vec2>) -> Self {
+        Self { vertices }
+    }
+
+    /// The corners of a `width` by `height` rectangle about `centre`, rotated
+    /// by `angle`, in winding order.
+    fn rectangle(
+        centre: Vec2,
+        width: f32,
+        height: f32,
+        angle: f32,
+    ) -> Vec<Vec2> {
+        let rot_vector = Vec2::from_angle(angle);
+        [
+            Vec2::new(width / 2_f32, height / 2_f32),
+            Vec2::new(width / 2_f32, -height / 2_f32),
+            Vec2::new(-width / 2_f32, -height / 2_f32),
+            Vec2::new(-width / 2_f32, height / 2_f32),
+        ]
+        .iter()
+        .map(|corner| centre + corner.rotate(rot_vector))
+        .collect()
+    }
+
+    pub fn vertices(&self) -> &[Vec2] {
+        &self.vertices
+    }
+
+    /// The mean of the pen's vertices.
+    pub fn centre(&self) -> Vec2 {
+        self.vertices.iter().sum::<Vec2>() / self.vertices.len().max(1) as f32
     }
 
+    fn edges(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        self.vertices
+            .iter()
+            .copied()
+            .zip(self.vertices.iter().copied().cycle().skip(1))
+    }
+
+    /// Whether `position` lies within the closed pen polygon, including its
+    /// open side. Points on an edge count as inside.
     pub fn contains(&self, position: Vec2) -> bool {
-        let distance =
-            (position - self.centre).rotate(Vec2::from_angle(self.angle));
-        distance.x < self.width / 2_f32 && distance.y < self.height / 2_f32
+        if self.edges().any(|(vertex_a, vertex_b)| {
+            let b_to_a = vertex_b - vertex_a;
+            let linear_position = ((position - vertex_a).dot(b_to_a)
+                / b_to_a.length_squared())
+            .clamp(0_f32, 1_f32);
+            (vertex_a + linear_position * b_to_a).distance_squared(position)
+                <= EDGE_EPSILON.powi(2)
+        }) {
+            return true;
+        }
+
+        self.edges()
+            .filter(|(vertex_a, vertex_b)| {
+                (vertex_a.y > position.y) != (vertex_b.y > position.y)
+                    && position.x
+                        < vertex_a.x
+                            + (position.y - vertex_a.y)
+                                * (vertex_b.x - vertex_a.x)
+                                / (vertex_b.y - vertex_a.y)
+            })
+            .count()
+            % 2
+            == 1
     }
 }
 
 #[derive(Bundle)]
 pub struct PenBundle {
     pen: Pen,
+    round: RoundScoped,
     #[bundle]
-    mesh: PbrBundle,
+    transform: TransformBundle,
 }
 
 impl PenBundle {
-    fn new(
-        centre: Vec2,
-        width: f32,
-        height: f32,
-        angle: f32,
-        mesh_assets: &mut Assets<Mesh>,
-        standard_material_assets: &mut Assets<StandardMaterial>,
-    ) -> Self {
-        let looking = centre + Vec2::from_angle(angle);
+    fn new(vertices: Vec<Vec2>) -> Self {
         Self {
-            pen: Pen::new(centre, width, height, angle),
-            mesh: PbrBundle {
-                mesh: mesh_assets.add(Mesh::from(shape::Box {
-                    min_x: -height / 2_f32,
-                    max_x: height / 2_f32,
-                    min_y: 0_f32,
-                    max_y: 0.1_f32,
-                    min_z: -width / 2_f32,
-                    max_z: width / 2_f32,
-                })),
-                material: standard_material_assets.add(StandardMaterial {
-                    base_color: Color::hsla(110.0, 0.5, 0.5, 0.2),
-                    emissive: Color::hsl(110.0, 1.0, 0.1),
-                    ..default()
-                }),
-                transform: Transform::from_xyz(centre.x, 0_f32, centre.y)
-                    .looking_at(
-                        Vec3::new(looking.x, 0_f32, looking.y),
-                        Vec3::Y,
-                    ),
-                ..default()
-            },
+            pen: Pen::new(vertices),
+            round: RoundScoped,
+            transform: TransformBundle::default(),
         }
     }
 
-    pub fn spawn(
-        commands: &mut Commands,
-        mesh_assets: &mut ResMut<Assets<Mesh>>,
-        standard_material_assets: &mut ResMut<Assets<StandardMaterial>>,
-    ) {
-        let centre = Vec2::new(
-            fastrand::f32() * 80_f32 - 40_f32,
-            fastrand::f32() * 80_f32 - 40_f32,
-        );
-        let width = fastrand::f32() * 10_f32 + 10_f32;
-        let height = fastrand::f32() * 10_f32 + 10_f32;
-        let angle = fastrand::f32() * 2_f32 * std::f32::consts::PI;
-
-        commands.spawn(Self::new(
-            centre,
-            width,
-            height,
-            angle,
-            mesh_assets,
-            standard_material_assets,
-        ));
+    /// Spawns a randomly placed pen and its barriers, returning the pen's
+    /// shape so the rest of the round can be laid out around it.
+    pub fn spawn(commands: &mut Commands, rng: &mut fastrand::Rng) -> Pen {
+        let centre =
+            Vec2::new(rng.f32() * 80_f32 - 40_f32, rng.f32() * 80_f32 - 40_f32);
+        let width = rng.f32() * 10_f32 + 10_f32;
+        let height = rng.f32() * 10_f32 + 10_f32;
+        let angle = rng.f32() * 2_f32 * std::f32::consts::PI;
 
-        let rot_vector = Vec2::from_angle(angle);
+        let corners = Pen::rectangle(centre, width, height, angle);
+
+        commands.spawn(Self::new(corners.clone()));
 
-        let rel_corners = [
-            centre
-                + Vec2::new(width / 2_f32, height / 2_f32).rotate(rot_vector),
-            centre
-                + Vec2::new(width / 2_f32, -height / 2_f32).rotate(rot_vector),
-            centre
-                + Vec2::new(-width / 2_f32, -height / 2_f32).rotate(rot_vector),
-            centre
-                + Vec2::new(-width / 2_f32, height / 2_f32).rotate(rot_vector),
-        ];
-
-        rel_corners.iter().zip(rel_corners.iter().skip(1)).for_each(
+        corners.iter().zip(corners.iter().skip(1)).for_each(
             |(vertex_a, vertex_b)| {
-                BarrierBundle::spawn(
-                    commands,
-                    *vertex_a,
-                    *vertex_b,
-                    mesh_assets,
-                    standard_material_assets,
-                );
+                let barrier =
+                    BarrierBundle::spawn(commands, *vertex_a, *vertex_b);
+                commands.entity(barrier).insert(RoundScoped);
             },
         );
+
+        Pen::new(corners)
+    }
+}
+
+/// A flat fan of triangles covering the (convex) pen polygon.
+fn pen_floor_mesh(pen: &Pen) -> Mesh {
+    let positions = pen
+        .vertices
+        .iter()
+        .map(|vertex| [vertex.x, 0.1_f32, vertex.y])
+        .collect::<Vec<_>>();
+    let normals = vec![[0_f32, 1_f32, 0_f32]; positions.len()];
+    let uvs = vec![[0_f32, 0_f32]; positions.len()];
+    let indices = (1..pen.vertices.len() as u32 - 1)
+        .flat_map(|index| [0, index + 1, index])
+        .collect();
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+fn add_pen_meshes(
+    mut commands: Commands,
+    pen_query: Query<(Entity, &Pen), Added<Pen>>,
+    mut mesh_assets: ResMut<Assets<Mesh>>,
+    mut standard_material_assets: ResMut<Assets<StandardMaterial>>,
+) {
+    pen_query.iter().for_each(|(entity, pen)| {
+        commands.entity(entity).insert((
+            mesh_assets.add(pen_floor_mesh(pen)),
+            standard_material_assets.add(StandardMaterial {
+                base_color: Color::hsla(110.0, 0.5, 0.5, 0.2),
+                emissive: Color::hsl(110.0, 1.0, 0.1),
+                double_sided: true,
+                cull_mode: None,
+                ..default()
+            }),
+            VisibilityBundle::default(),
+        ));
+    });
+}
+
+pub struct PenRenderPlugin;
+
+impl Plugin for PenRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(add_pen_meshes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_4;
+
+    use super::*;
+
+    /// A 10 by 6 pen about `centre`, rotated by `angle`. Its open side runs
+    /// from the last corner back to the first.
+    fn pen(centre: Vec2, angle: f32) -> Pen {
+        Pen::new(Pen::rectangle(centre, 10_f32, 6_f32, angle))
+    }
+
+    /// Maps a point given in the unrotated pen's frame into the world.
+    fn place(centre: Vec2, angle: f32, local: Vec2) -> Vec2 {
+        centre + local.rotate(Vec2::from_angle(angle))
+    }
+
+    #[test]
+    fn axis_aligned_inside_and_outside() {
+        let pen = pen(Vec2::ZERO, 0_f32);
+        assert!(pen.contains(Vec2::ZERO));
+        assert!(pen.contains(Vec2::new(4.9, 2.9)));
+        assert!(pen.contains(Vec2::new(-4.9, -2.9)));
+        assert!(!pen.contains(Vec2::new(5.1, 0_f32)));
+        assert!(!pen.contains(Vec2::new(0_f32, -3.1)));
+        assert!(!pen.contains(Vec2::new(20_f32, 20_f32)));
+    }
+
+    #[test]
+    fn rotated_inside_and_outside() {
+        let centre = Vec2::new(12_f32, -7_f32);
+        let pen = pen(centre, FRAC_PI_4);
+        assert!(pen.contains(centre));
+        assert!(pen.contains(place(centre, FRAC_PI_4, Vec2::new(4.9, 2.9))));
+        assert!(pen.contains(place(centre, FRAC_PI_4, Vec2::new(-4.9, -2.9))));
+        assert!(!pen.contains(place(centre, FRAC_PI_4, Vec2::new(5.1, 0_f32))));
+        assert!(!pen.contains(place(centre, FRAC_PI_4, Vec2::new(0_f32, 3.1))));
+        // Inside the unrotated rectangle's bounds, but outside once rotated.
+        assert!(!pen.contains(centre + Vec2::new(4.9, 2.9)));
+    }
+
+    #[test]
+    fn points_on_closed_edges_are_inside() {
+        [0_f32, FRAC_PI_4].into_iter().for_each(|angle| {
+            let pen = pen(Vec2::ZERO, angle);
+            pen.edges().take(pen.vertices().len() - 1).for_each(
+                |(vertex_a, vertex_b)| {
+                    [0_f32, 0.25, 0.5, 1_f32].into_iter().for_each(|t| {
+                        let point = vertex_a.lerp(vertex_b, t);
+                        assert!(
+                            pen.contains(point),
+                            "{point} on edge {vertex_a}-{vertex_b}"
+                        );
+                    })
+                },
+            )
+        })
+    }
+
+    #[test]
+    fn open_side() {
+        [0_f32, FRAC_PI_4].into_iter().for_each(|angle| {
+            let pen = pen(Vec2::ZERO, angle);
+            let vertices = pen.vertices();
+            let (gate_a, gate_b) = (vertices[vertices.len() - 1], vertices[0]);
+            let gate_mid = gate_a.lerp(gate_b, 0.5);
+            let inward = (Vec2::ZERO - gate_mid).normalize();
+
+            assert!(pen.contains(gate_mid));
+            assert!(pen.contains(gate_mid + inward * 0.1));
+            assert!(!pen.contains(gate_mid - inward * 0.1));
+        })
     }
 }
@@ -1,82 +1,258 @@
-use std::f32::consts::PI;
-
 use bevy::prelude::{shape::Capsule, *};
-use leafwing_input_manager::{orientation::Direction, prelude::*};
+use iyes_loopless::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    barrier::Barrier,
+    camera::MainCameraTag,
+    common::{ground_position, MaxSpeed, Presence, Threat},
+    settings::{Bindings, BindingsPlugin, Rebindable},
+    sheep::{SheepTag, SHEEP_RADIUS},
+    simulation::{
+        step_seconds, Interpolated, SIMULATION_SUBSTAGE, SIMULATION_TIMESTEP,
+    },
+};
+
+/// The radius of the player's body, used for collisions with barriers and
+/// sheep.
+pub const PLAYER_RADIUS: f32 = 0.5;
+
+/// The fraction of an overlap with a sheep which pushes the player back each
+/// frame, the sheep being nudged away to resolve the rest.
+const SHEEP_PUSH_BACK: f32 = 0.5;
 
-use crate::{camera::MainCameraTag, common::MaxSpeed};
+const WALK_SPEED: f32 = 10.0;
+const SPRINT_SPEED: f32 = 16.0;
+/// The seconds of sprinting a full stamina pool allows.
+pub const STAMINA_MAX: f32 = 3.0;
+/// The seconds of sprinting recovered per second of rest.
+const STAMINA_RECOVERY: f32 = 0.5;
+/// The stamina needed to start sprinting again after running dry.
+const STAMINA_TO_SPRINT: f32 = 0.5;
+/// How much further off sheep shy from a sprinting shepherd.
+const SPRINT_PRESENCE: f32 = 1.8;
+
+/// How far the shepherd's shout carries, well beyond the range at which
+/// sheep otherwise shy away from the shepherd.
+pub const SHOUT_RADIUS: f32 = 30.0;
+/// The seconds the shepherd must wait between shouts.
+const SHOUT_COOLDOWN: f32 = 5.0;
+
+/// How close a sheep must be for the shepherd to hook it with the crook.
+const CROOK_REACH: f32 = 2.5;
+/// A hooked sheep pulled further than this from the shepherd slips free.
+const CROOK_BREAK_DISTANCE: f32 = 5.0;
+/// The shepherd's top speed while dragging a sheep, which the sheep is made
+/// to follow at.
+pub const CROOK_SPEED: f32 = 4.0;
+/// How far behind the shepherd a hooked sheep is held.
+pub const CROOK_LENGTH: f32 = 1.5;
 
 #[derive(Component)]
 pub struct PlayerTag;
 
-#[derive(Actionlike, Copy, Clone, Debug)]
-enum PlayerMovementAction {
+/// Movement requested by touch or mouse rather than through the input map.
+#[derive(Component, Default)]
+pub struct PointerMovement {
+    /// The virtual joystick's deflection as (right, forward) relative to the
+    /// camera, of at most unit length.
+    pub joystick: Vec2,
+    /// A point on the ground to walk towards, cleared on arrival or when
+    /// steered by any other input.
+    pub target: Option<Vec2>,
+}
+
+#[derive(
+    Actionlike,
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+pub enum PlayerMovementAction {
     Forward,
     Backward,
     Left,
     Right,
+    /// Analog movement, read from its axis pair rather than as a direction.
+    Move,
+    /// A shout or whistle to scatter the sheep in earshot.
+    Shout,
+    /// Hooks the nearest sheep with the crook, dragging it while held.
+    Crook,
+    /// Runs faster for as long as stamina lasts.
+    Sprint,
 }
 
 impl PlayerMovementAction {
-    fn direction(self) -> Direction {
+    /// The direction of movement as (right, forward) relative to the camera.
+    fn direction(self) -> Vec2 {
         match self {
-            PlayerMovementAction::Forward => Direction::NORTH,
-            PlayerMovementAction::Backward => Direction::SOUTH,
-            PlayerMovementAction::Left => Direction::EAST,
-            PlayerMovementAction::Right => Direction::WEST,
+            PlayerMovementAction::Forward => Vec2::Y,
+            PlayerMovementAction::Backward => Vec2::NEG_Y,
+            PlayerMovementAction::Left => Vec2::NEG_X,
+            PlayerMovementAction::Right => Vec2::X,
+            PlayerMovementAction::Move
+            | PlayerMovementAction::Shout
+            | PlayerMovementAction::Crook
+            | PlayerMovementAction::Sprint => Vec2::ZERO,
         }
     }
 }
 
-#[derive(Bundle)]
-pub struct PlayerBundle {
-    tag: PlayerTag,
-    #[bundle]
-    mesh: PbrBundle,
-    #[bundle]
-    input_manager: InputManagerBundle<PlayerMovementAction>,
-    speed: MaxSpeed,
-}
+impl Rebindable for PlayerMovementAction {
+    const STORAGE_KEY: &'static str = "player_bindings";
 
-impl PlayerBundle {
-    fn default_input_map() -> InputMap<PlayerMovementAction> {
+    fn default_input_map() -> InputMap<Self> {
         let mut input_map = InputMap::default();
 
         input_map.insert(KeyCode::W, PlayerMovementAction::Forward);
         input_map.insert(KeyCode::S, PlayerMovementAction::Backward);
         input_map.insert(KeyCode::A, PlayerMovementAction::Left);
         input_map.insert(KeyCode::D, PlayerMovementAction::Right);
+        input_map.insert(KeyCode::Space, PlayerMovementAction::Shout);
+        input_map.insert(KeyCode::G, PlayerMovementAction::Crook);
+        input_map.insert(KeyCode::LShift, PlayerMovementAction::Sprint);
+
+        // No gamepad is associated with the map, so these follow whichever
+        // gamepad is connected first, including ones plugged in mid-game.
+        input_map.insert(DualAxis::left_stick(), PlayerMovementAction::Move);
+        input_map
+            .insert(GamepadButtonType::DPadUp, PlayerMovementAction::Forward);
+        input_map.insert(
+            GamepadButtonType::DPadDown,
+            PlayerMovementAction::Backward,
+        );
+        input_map
+            .insert(GamepadButtonType::DPadLeft, PlayerMovementAction::Left);
+        input_map
+            .insert(GamepadButtonType::DPadRight, PlayerMovementAction::Right);
+        input_map.insert(
+            GamepadButtonType::RightTrigger,
+            PlayerMovementAction::Shout,
+        );
+        input_map.insert(
+            GamepadButtonType::LeftTrigger,
+            PlayerMovementAction::Crook,
+        );
+        input_map
+            .insert(GamepadButtonType::LeftThumb, PlayerMovementAction::Sprint);
 
         input_map
     }
 
-    fn new(
-        mesh: Handle<Mesh>,
-        material: Handle<StandardMaterial>,
-        position: Vec2,
-    ) -> Self {
+    fn rebindable(self) -> bool {
+        self != PlayerMovementAction::Move
+    }
+}
+
+/// The camera's forward and right directions projected onto the ground
+/// plane. When looking straight down, forward is taken from the top of the
+/// view instead.
+fn ground_axes(camera: &Transform) -> (Vec2, Vec2) {
+    let forward = [camera.forward(), camera.up()]
+        .iter()
+        .find_map(|direction| {
+            Vec2::new(direction.x, direction.z).try_normalize()
+        })
+        .unwrap_or(Vec2::NEG_Y);
+    (forward, Vec2::new(-forward.y, forward.x))
+}
+
+/// The shepherd's shout, which sends every sheep within [`SHOUT_RADIUS`]
+/// scattering away.
+#[derive(Component, Default)]
+pub struct Shout {
+    /// Seconds until the shepherd can shout again.
+    pub cooldown: f32,
+    /// Whether the shepherd shouted this step.
+    pub sounding: bool,
+}
+
+/// The shepherd's crook, and the sheep hooked on it, if any.
+#[derive(Component, Default)]
+pub struct Crook {
+    pub held: Option<Entity>,
+}
+
+/// The shepherd's reserve of sprinting, in seconds.
+#[derive(Component)]
+pub struct Stamina {
+    pub remaining: f32,
+    pub sprinting: bool,
+}
+
+impl Default for Stamina {
+    fn default() -> Self {
+        Self {
+            remaining: STAMINA_MAX,
+            sprinting: false,
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub struct PlayerBundle {
+    tag: PlayerTag,
+    threat: Threat,
+    #[bundle]
+    transform: TransformBundle,
+    interpolated: Interpolated,
+    #[bundle]
+    input_manager: InputManagerBundle<PlayerMovementAction>,
+    pointer: PointerMovement,
+    shout: Shout,
+    crook: Crook,
+    stamina: Stamina,
+    presence: Presence,
+    speed: MaxSpeed,
+}
+
+impl PlayerBundle {
+    fn new(position: Vec2, input_map: InputMap<PlayerMovementAction>) -> Self {
+        let transform = Transform::from_xyz(position.x, 1.0, position.y);
         Self {
             tag: PlayerTag,
-            mesh: PbrBundle {
-                mesh,
-                material,
-                transform: Transform::from_xyz(position.x, 1.0, position.y),
-                ..default()
-            },
+            threat: Threat,
+            transform: TransformBundle::from_transform(transform),
+            interpolated: Interpolated::new(transform),
             input_manager: InputManagerBundle {
                 action_state: ActionState::default(),
-                input_map: Self::default_input_map(),
+                input_map,
             },
-            speed: MaxSpeed::new(10.0),
+            pointer: PointerMovement::default(),
+            shout: Shout::default(),
+            crook: Crook::default(),
+            stamina: Stamina::default(),
+            presence: Presence(1_f32),
+            speed: MaxSpeed::new(WALK_SPEED),
         }
     }
 
     pub fn spawn(
         commands: &mut Commands,
-        mesh_assets: &mut ResMut<Assets<Mesh>>,
-        standard_material_assets: &mut ResMut<Assets<StandardMaterial>>,
         position: Vec2,
+        bindings: &Bindings<PlayerMovementAction>,
     ) {
-        commands.spawn(PlayerBundle::new(
+        commands.spawn(PlayerBundle::new(position, bindings.0.clone()));
+    }
+}
+
+fn add_player_meshes(
+    mut commands: Commands,
+    player_query: Query<Entity, Added<PlayerTag>>,
+    mut mesh_assets: ResMut<Assets<Mesh>>,
+    mut standard_material_assets: ResMut<Assets<StandardMaterial>>,
+) {
+    player_query.iter().for_each(|entity| {
+        commands.entity(entity).insert((
             mesh_assets.add(Mesh::from(Capsule {
                 radius: 0.5,
                 depth: 1.0,
@@ -84,9 +260,9 @@ impl PlayerBundle {
             })),
             standard_material_assets
                 .add(StandardMaterial::from(Color::hsl(300.0, 0.5, 0.5))),
-            position,
+            VisibilityBundle::default(),
         ));
-    }
+    });
 }
 
 #[allow(clippy::type_complexity)]
@@ -95,37 +271,288 @@ fn move_player(
         (
             &mut Transform,
             &ActionState<PlayerMovementAction>,
+            &mut PointerMovement,
             &MaxSpeed,
+            &Crook,
         ),
         (With<PlayerTag>, Without<MainCameraTag>),
     >,
     camera_query: Query<&Transform, (With<MainCameraTag>, Without<PlayerTag>)>,
-    time: Res<Time>,
+    sheep_query: Query<&Transform, (With<SheepTag>, Without<PlayerTag>)>,
+    barrier_query: Query<&Barrier>,
+    timesteps: Res<FixedTimesteps>,
 ) {
-    let camera_angle = camera_query.single().rotation.y - PI / 2_f32;
-    player_query
-        .iter_mut()
-        .for_each(|(mut transform, action, max_speed)| {
-            let direction = action
+    let (forward, right) =
+        ground_axes(&camera_query.get_single().copied().unwrap_or_default());
+    player_query.iter_mut().for_each(
+        |(mut transform, action, mut pointer, max_speed, crook)| {
+            let digital = action
                 .get_pressed()
                 .iter()
-                .fold(Vec2::ZERO, |acc, action| {
-                    acc + Vec2::from(action.direction())
-                })
-                .normalize_or_zero()
-                .rotate(Vec2::from_angle(camera_angle))
-                * time.delta_seconds()
-                * max_speed.0;
-            transform.translation.x += direction.x;
-            transform.translation.z += direction.y;
+                .fold(Vec2::ZERO, |acc, action| acc + action.direction())
+                .normalize_or_zero();
+            let analog = action
+                .axis_pair(PlayerMovementAction::Move)
+                .map(|axis_pair| axis_pair.xy())
+                .unwrap_or_default();
+            let input =
+                (digital + analog + pointer.joystick).clamp_length_max(1_f32);
+            let max_speed = match crook.held {
+                Some(_) => max_speed.0.min(CROOK_SPEED),
+                None => max_speed.0,
+            };
+            let step = step_seconds(&timesteps) * max_speed;
+            let start = ground_position(&transform);
+            if input != Vec2::ZERO {
+                pointer.target = None;
+            }
+            let direction = match pointer.target {
+                Some(target) if target.distance(start) <= step => {
+                    pointer.target = None;
+                    target - start
+                }
+                Some(target) => (target - start).normalize_or_zero() * step,
+                None => (right * input.x + forward * input.y) * step,
+            };
+            let end = sheep_query.iter().fold(
+                start + direction,
+                |end, sheep_transform| {
+                    let seperation = end - ground_position(sheep_transform);
+                    let overlap =
+                        PLAYER_RADIUS + SHEEP_RADIUS - seperation.length();
+                    if overlap > 0_f32 {
+                        end + seperation.normalize_or_zero()
+                            * overlap
+                            * SHEEP_PUSH_BACK
+                    } else {
+                        end
+                    }
+                },
+            );
+            let end = barrier_query.iter().fold(end, |end, barrier| {
+                barrier.constrain_motion(start, end, PLAYER_RADIUS)
+            });
+            transform.translation.x = end.x;
+            transform.translation.z = end.y;
+        },
+    )
+}
+
+#[derive(SystemLabel)]
+pub struct MovePlayerLabel;
+
+fn shout(
+    mut player_query: Query<
+        (&mut Shout, &ActionState<PlayerMovementAction>),
+        With<PlayerTag>,
+    >,
+    timesteps: Res<FixedTimesteps>,
+) {
+    let delta_seconds = step_seconds(&timesteps);
+    player_query.iter_mut().for_each(|(mut shout, action)| {
+        shout.cooldown = (shout.cooldown - delta_seconds).max(0_f32);
+        shout.sounding = shout.cooldown == 0_f32
+            && action.pressed(PlayerMovementAction::Shout);
+        if shout.sounding {
+            shout.cooldown = SHOUT_COOLDOWN;
+        }
+    })
+}
+
+#[derive(SystemLabel)]
+pub struct ShoutLabel;
+
+/// Speeds the shepherd up while sprinting, spending stamina, and recovers
+/// stamina otherwise. A shepherd who runs dry must rest a little before
+/// sprinting again.
+fn sprint(
+    mut player_query: Query<
+        (
+            &mut Stamina,
+            &mut MaxSpeed,
+            &mut Presence,
+            &ActionState<PlayerMovementAction>,
+        ),
+        With<PlayerTag>,
+    >,
+    timesteps: Res<FixedTimesteps>,
+) {
+    let delta_seconds = step_seconds(&timesteps);
+    player_query.iter_mut().for_each(
+        |(mut stamina, mut max_speed, mut presence, action)| {
+            let needed = if stamina.sprinting {
+                0_f32
+            } else {
+                STAMINA_TO_SPRINT
+            };
+            stamina.sprinting = action.pressed(PlayerMovementAction::Sprint)
+                && stamina.remaining > needed;
+            stamina.remaining = if stamina.sprinting {
+                (stamina.remaining - delta_seconds).max(0_f32)
+            } else {
+                (stamina.remaining + STAMINA_RECOVERY * delta_seconds)
+                    .min(STAMINA_MAX)
+            };
+
+            (max_speed.0, presence.0) = if stamina.sprinting {
+                (SPRINT_SPEED, SPRINT_PRESENCE)
+            } else {
+                (WALK_SPEED, 1_f32)
+            };
+        },
+    )
+}
+
+#[derive(SystemLabel)]
+pub struct SprintLabel;
+
+/// Hooks the nearest sheep in reach while the crook is held, letting it go
+/// when the crook is released or the sheep is pulled too far away.
+fn use_crook(
+    mut player_query: Query<
+        (&mut Crook, &Transform, &ActionState<PlayerMovementAction>),
+        With<PlayerTag>,
+    >,
+    sheep_query: Query<(Entity, &Transform), With<SheepTag>>,
+) {
+    player_query
+        .iter_mut()
+        .for_each(|(mut crook, player_transform, action)| {
+            let position = ground_position(player_transform);
+            let distance = |sheep_transform: &Transform| {
+                ground_position(sheep_transform).distance(position)
+            };
+            crook.held = if !action.pressed(PlayerMovementAction::Crook) {
+                None
+            } else if let Some(held) = crook.held {
+                sheep_query
+                    .get(held)
+                    .ok()
+                    .filter(|(_, sheep_transform)| {
+                        distance(sheep_transform) <= CROOK_BREAK_DISTANCE
+                    })
+                    .map(|(entity, _)| entity)
+            } else {
+                sheep_query
+                    .iter()
+                    .map(|(entity, sheep_transform)| {
+                        (entity, distance(sheep_transform))
+                    })
+                    .filter(|(_, distance)| *distance < CROOK_REACH)
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .map(|(entity, _)| entity)
+            };
         })
 }
 
+#[derive(SystemLabel)]
+pub struct CrookLabel;
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Bindings<PlayerMovementAction>>()
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                move_player
+                    .label(MovePlayerLabel)
+                    .after(CrookLabel)
+                    .after(SprintLabel),
+            )
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                sprint.label(SprintLabel),
+            )
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                use_crook.label(CrookLabel),
+            )
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                shout.label(ShoutLabel),
+            );
+    }
+}
+
+/// Drives the player's actions from keyboard and gamepad input, with the
+/// player's own key bindings.
+pub struct PlayerInputPlugin;
+
+impl Plugin for PlayerInputPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(InputManagerPlugin::<PlayerMovementAction>::default())
-            .add_system(move_player);
+            .add_plugin(BindingsPlugin::<PlayerMovementAction>::default());
+    }
+}
+
+pub struct PlayerRenderPlugin;
+
+impl Plugin for PlayerRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(add_player_meshes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    /// The ground-plane projection of a camera direction, normalised.
+    fn on_ground(direction: Vec3) -> Vec2 {
+        Vec2::new(direction.x, direction.z).normalize()
+    }
+
+    fn assert_axes(camera: &Transform) -> (Vec2, Vec2) {
+        let (forward, right) = ground_axes(camera);
+        assert!((forward.length() - 1_f32).abs() < EPSILON, "{forward}");
+        assert!((right.length() - 1_f32).abs() < EPSILON, "{right}");
+        assert!(forward.dot(right).abs() < EPSILON, "{forward} {right}");
+        (forward, right)
+    }
+
+    #[test]
+    fn yawed_cameras() {
+        [
+            (0_f32, Vec2::NEG_Y, Vec2::X),
+            (FRAC_PI_2, Vec2::NEG_X, Vec2::NEG_Y),
+            (PI, Vec2::Y, Vec2::NEG_X),
+        ]
+        .into_iter()
+        .for_each(|(yaw, expected_forward, expected_right)| {
+            let camera = Transform::from_rotation(Quat::from_rotation_y(yaw));
+            let (forward, right) = assert_axes(&camera);
+            assert!(forward.abs_diff_eq(expected_forward, EPSILON), "{yaw}");
+            assert!(right.abs_diff_eq(expected_right, EPSILON), "{yaw}");
+            assert!(right.abs_diff_eq(on_ground(camera.right()), EPSILON));
+        })
+    }
+
+    #[test]
+    fn tilted_camera() {
+        let camera = Transform::from_xyz(5_f32, 20_f32, 12_f32)
+            .looking_at(Vec3::new(-3_f32, 0_f32, 1_f32), Vec3::Y);
+        let (forward, right) = assert_axes(&camera);
+        assert!(forward.abs_diff_eq(on_ground(camera.forward()), EPSILON));
+        assert!(right.abs_diff_eq(on_ground(camera.right()), EPSILON));
+    }
+
+    #[test]
+    fn straight_down_camera() {
+        [Vec3::NEG_Z, Vec3::X].into_iter().for_each(|up| {
+            let camera = Transform::from_xyz(0_f32, 30_f32, 0_f32)
+                .looking_at(Vec3::ZERO, up);
+            let (forward, right) = assert_axes(&camera);
+            assert!(forward.abs_diff_eq(on_ground(up), EPSILON), "{up}");
+            assert!(right.abs_diff_eq(on_ground(camera.right()), EPSILON));
+        })
     }
 }
@@ -0,0 +1,192 @@
+use bevy::{input::InputSystem, prelude::*};
+
+use crate::camera::MainCameraTag;
+use crate::player::{PlayerTag, PointerMovement};
+
+/// How far, in logical pixels, a touch may stray from where it started and
+/// still count as a tap.
+const TAP_DISTANCE: f32 = 12.0;
+/// The distance, in logical pixels, a touch must be dragged to deflect the
+/// virtual joystick fully.
+const JOYSTICK_RADIUS: f32 = 60.0;
+const KNOB_SIZE: f32 = 40.0;
+
+/// The touch currently steering the virtual joystick, if any.
+#[derive(Resource, Default)]
+struct Joystick(Option<u64>);
+
+#[derive(Component)]
+struct JoystickBase;
+
+#[derive(Component)]
+struct JoystickKnob;
+
+/// The point on the ground plane under a position in the primary window,
+/// measured from its bottom left.
+fn ground_point(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    viewport_position: Vec2,
+) -> Option<Vec2> {
+    let ray = camera.viewport_to_world(camera_transform, viewport_position)?;
+    (ray.direction.y < 0_f32).then(|| {
+        let point = ray.origin - ray.direction * ray.origin.y / ray.direction.y;
+        Vec2::new(point.x, point.z)
+    })
+}
+
+fn spawn_joystick(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::new(
+                        Val::Px(JOYSTICK_RADIUS * 2_f32),
+                        Val::Px(JOYSTICK_RADIUS * 2_f32),
+                    ),
+                    ..default()
+                },
+                background_color: Color::rgba(1.0, 1.0, 1.0, 0.15).into(),
+                visibility: Visibility::INVISIBLE,
+                ..default()
+            },
+            JoystickBase,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        size: Size::new(Val::Px(KNOB_SIZE), Val::Px(KNOB_SIZE)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(1.0, 1.0, 1.0, 0.5).into(),
+                    ..default()
+                },
+                JoystickKnob,
+            ));
+        });
+}
+
+/// Turns dragged touches into virtual joystick movement, and taps or left
+/// clicks into a target on the ground to walk towards.
+fn pointer_input(
+    mut player_query: Query<&mut PointerMovement, With<PlayerTag>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCameraTag>>,
+    interaction_query: Query<&Interaction>,
+    mut joystick: ResMut<Joystick>,
+    touches: Res<Touches>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+) {
+    let Some(window) = windows.get_primary() else {
+        return;
+    };
+
+    // Touch positions are measured from the top left of the window.
+    let tap = touches
+        .iter_just_released()
+        .filter(|touch| Some(touch.id()) != joystick.0)
+        .find(|touch| touch.distance().length() <= TAP_DISTANCE)
+        .map(|touch| {
+            Vec2::new(touch.position().x, window.height() - touch.position().y)
+        });
+
+    // Two fingers pinch the camera instead.
+    if touches.iter().count() > 1 {
+        joystick.0 = None;
+    } else if joystick.0.is_none() {
+        joystick.0 = touches
+            .iter()
+            .find(|touch| touch.distance().length() > TAP_DISTANCE)
+            .map(|touch| touch.id());
+    }
+    joystick.0 = joystick.0.filter(|&id| touches.get_pressed(id).is_some());
+    let deflection = joystick.0.and_then(|id| touches.get_pressed(id)).map_or(
+        Vec2::ZERO,
+        |touch| {
+            let offset = touch.distance() / JOYSTICK_RADIUS;
+            Vec2::new(offset.x, -offset.y).clamp_length_max(1_f32)
+        },
+    );
+    // Clicks on buttons are left to them.
+    let click = (mouse_buttons.just_pressed(MouseButton::Left)
+        && interaction_query
+            .iter()
+            .all(|interaction| *interaction == Interaction::None))
+    .then(|| window.cursor_position())
+    .flatten();
+    let target = tap.or(click).and_then(|position| {
+        camera_query
+            .get_single()
+            .ok()
+            .and_then(|(camera, transform)| {
+                ground_point(camera, transform, position)
+            })
+    });
+
+    player_query.iter_mut().for_each(|mut pointer| {
+        pointer.joystick = deflection;
+        if target.is_some() {
+            pointer.target = target;
+        }
+    })
+}
+
+#[allow(clippy::type_complexity)]
+fn update_joystick(
+    mut base_query: Query<
+        (&mut Style, &mut Visibility),
+        (With<JoystickBase>, Without<JoystickKnob>),
+    >,
+    mut knob_query: Query<&mut Style, With<JoystickKnob>>,
+    joystick: Res<Joystick>,
+    touches: Res<Touches>,
+    ui_scale: Res<UiScale>,
+) {
+    let touch = joystick.0.and_then(|id| touches.get_pressed(id));
+    let scale = ui_scale.scale as f32;
+    base_query
+        .iter_mut()
+        .for_each(|(mut style, mut visibility)| {
+            visibility.is_visible = touch.is_some();
+            if let Some(touch) = touch {
+                let corner = touch.start_position() / scale
+                    - Vec2::splat(JOYSTICK_RADIUS);
+                style.position = UiRect {
+                    left: Val::Px(corner.x),
+                    top: Val::Px(corner.y),
+                    ..default()
+                };
+            }
+        });
+    knob_query.iter_mut().for_each(|mut style| {
+        if let Some(touch) = touch {
+            let offset = (touch.distance() / scale)
+                .clamp_length_max(JOYSTICK_RADIUS)
+                + Vec2::splat(JOYSTICK_RADIUS - KNOB_SIZE / 2_f32);
+            style.position = UiRect {
+                left: Val::Px(offset.x),
+                top: Val::Px(offset.y),
+                ..default()
+            };
+        }
+    });
+}
+
+/// Steers the player with a touch joystick, and walks them to wherever the
+/// ground is tapped or clicked.
+pub struct PointerInputPlugin;
+
+impl Plugin for PointerInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Joystick>()
+            .add_startup_system(spawn_joystick)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                pointer_input.after(InputSystem),
+            )
+            .add_system(update_joystick);
+    }
+}
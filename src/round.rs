@@ -0,0 +1,222 @@
+use bevy::{prelude::*, time::Stopwatch};
+use iyes_loopless::prelude::*;
+
+use crate::archetype::ArchetypeWeights;
+use crate::common::{ground_position, LevelSeed, RoundScoped};
+use crate::dog::{DogAction, DogBundle};
+use crate::field::Field;
+use crate::flocking::{ActiveFlockingConfig, FlockingConfig};
+use crate::pen::{Pen, PenBundle};
+use crate::player::{PlayerBundle, PlayerMovementAction};
+use crate::settings::Bindings;
+use crate::sheep::{SheepBundle, SheepTag};
+use crate::wolf::WolfBundle;
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum GameState {
+    Playing,
+    Success,
+    /// Every sheep was lost, and the round is played again.
+    Failure,
+}
+
+#[derive(Component)]
+pub struct RoundManager(usize);
+
+impl RoundManager {
+    /// Starts on the first round, round zero being the field itself.
+    fn new() -> Self {
+        Self(1)
+    }
+
+    pub fn round(&self) -> usize {
+        self.0
+    }
+
+    fn next_level(&mut self) {
+        self.0 += 1;
+    }
+
+    /// Wolves join from the third round, one more every third round after.
+    fn wolf_count(&self) -> usize {
+        (self.0 / 3).min(3)
+    }
+
+    fn get_cluster_sizes(&self, rng: &mut fastrand::Rng) -> Vec<usize> {
+        (0..self.0).map(|_| rng.usize(1..self.0 + 1)).collect()
+    }
+}
+
+/// Time spent playing the current round.
+#[derive(Resource, Default)]
+pub struct RoundClock(pub Stopwatch);
+
+/// The pause between a round being won and the next one starting.
+#[derive(Resource)]
+struct Interlude(Timer);
+
+impl Interlude {
+    fn new() -> Self {
+        Self(Timer::from_seconds(3_f32, TimerMode::Once))
+    }
+}
+
+/// Rolls positions until one lies outside the pen, so that no sheep starts
+/// the round already penned.
+fn outside_pen(pen: &Pen, roll: impl FnMut() -> Vec2) -> Vec2 {
+    std::iter::repeat_with(roll)
+        .find(|position| !pen.contains(*position))
+        .unwrap()
+}
+
+fn spawn_cluster(
+    commands: &mut Commands,
+    rng: &mut fastrand::Rng,
+    pen: &Pen,
+    count: usize,
+    weights: &ArchetypeWeights,
+    config: &FlockingConfig,
+) {
+    let cluster_position = outside_pen(pen, || {
+        Vec2::new(rng.f32() * 80_f32 - 40_f32, rng.f32() * 80_f32 - 40_f32)
+    });
+    (0..count).for_each(|_| {
+        let position = outside_pen(pen, || {
+            cluster_position
+                + Vec2::new(
+                    rng.f32() * 10_f32 - 5_f32,
+                    rng.f32() * 10_f32 - 5_f32,
+                )
+        });
+        SheepBundle::spawn(
+            commands,
+            position,
+            weights.choose(rng),
+            config,
+            rng,
+        );
+    });
+}
+
+/// Wins the round once every remaining sheep is penned. Sheep taken by
+/// wolves are simply lost, but a round with none left at all is lost too.
+fn check_win(
+    pen_query: Query<&Pen>,
+    sheep_query: Query<&Transform, With<SheepTag>>,
+    mut commands: Commands,
+) {
+    if sheep_query.is_empty() {
+        commands.insert_resource(NextState(GameState::Failure));
+    } else if let Ok(pen) = pen_query.get_single() {
+        if sheep_query
+            .iter()
+            .all(|transform| pen.contains(ground_position(transform)))
+        {
+            commands.insert_resource(NextState(GameState::Success))
+        }
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    seed: Res<LevelSeed>,
+    bindings: Res<Bindings<PlayerMovementAction>>,
+    dog_bindings: Res<Bindings<DogAction>>,
+) {
+    info!("Level seed: {}", seed.0);
+
+    commands.spawn(RoundManager::new());
+
+    Field::spawn(&mut commands, &mut seed.rng(0));
+
+    PlayerBundle::spawn(&mut commands, Vec2::new(0_f32, 0_f32), &bindings);
+    DogBundle::spawn(&mut commands, Vec2::new(-3_f32, 0_f32), &dog_bindings);
+}
+
+fn start_round(
+    mut commands: Commands,
+    round_manager_query: Query<&RoundManager>,
+    active_config: Res<ActiveFlockingConfig>,
+    seed: Res<LevelSeed>,
+    mut round_clock: ResMut<RoundClock>,
+) {
+    let round_manager = round_manager_query.single();
+    round_clock.0.reset();
+    let mut rng = seed.rng(round_manager.0);
+
+    let pen = PenBundle::spawn(&mut commands, &mut rng);
+
+    let weights = ArchetypeWeights::for_round(round_manager.0);
+
+    round_manager
+        .get_cluster_sizes(&mut rng)
+        .iter()
+        .for_each(|&count| {
+            spawn_cluster(
+                &mut commands,
+                &mut rng,
+                &pen,
+                count,
+                &weights,
+                &active_config.config,
+            )
+        });
+
+    (0..round_manager.wolf_count()).for_each(|_| {
+        WolfBundle::spawn(
+            &mut commands,
+            Vec2::from_angle(rng.f32() * std::f32::consts::TAU) * 40_f32,
+        )
+    });
+}
+
+fn tick_round_clock(mut round_clock: ResMut<RoundClock>, time: Res<Time>) {
+    round_clock.0.tick(time.delta());
+}
+
+fn cleanup_round(
+    mut commands: Commands,
+    round_query: Query<Entity, With<RoundScoped>>,
+) {
+    round_query.iter().for_each(|entity| {
+        commands.entity(entity).despawn_recursive();
+    });
+}
+
+fn advance_round(mut round_manager_query: Query<&mut RoundManager>) {
+    round_manager_query.single_mut().next_level();
+}
+
+fn start_interlude(mut commands: Commands) {
+    commands.insert_resource(Interlude::new());
+}
+
+fn tick_interlude(
+    mut commands: Commands,
+    mut interlude: ResMut<Interlude>,
+    time: Res<Time>,
+) {
+    if interlude.0.tick(time.delta()).finished() {
+        commands.remove_resource::<Interlude>();
+        commands.insert_resource(NextState(GameState::Playing));
+    }
+}
+
+pub struct RoundPlugin;
+
+impl Plugin for RoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelSeed>()
+            .init_resource::<RoundClock>()
+            .add_loopless_state(GameState::Playing)
+            .add_enter_system(GameState::Playing, start_round)
+            .add_exit_system(GameState::Playing, cleanup_round)
+            .add_system(check_win.run_in_state(GameState::Playing))
+            .add_system(tick_round_clock.run_in_state(GameState::Playing))
+            .add_enter_system(GameState::Success, advance_round)
+            .add_enter_system(GameState::Success, start_interlude)
+            .add_enter_system(GameState::Failure, start_interlude)
+            .add_system(tick_interlude.run_not_in_state(GameState::Playing))
+            .add_startup_system(setup);
+    }
+}
@@ -0,0 +1,523 @@
+use std::{fmt::Debug, hash::Hash, marker::PhantomData};
+
+use bevy::{prelude::*, utils::HashMap};
+use leafwing_input_manager::{prelude::*, user_input::InputKind};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::storage;
+
+/// An action set whose keyboard bindings players may change, and which are
+/// remembered between sessions.
+pub trait Rebindable:
+    Actionlike + Copy + Debug + Ord + Hash + Serialize + DeserializeOwned
+{
+    /// The name the bindings are stored under.
+    const STORAGE_KEY: &'static str;
+
+    fn default_input_map() -> InputMap<Self>;
+
+    /// Whether the action is driven by a key, and so is listed on the
+    /// settings screen.
+    fn rebindable(self) -> bool {
+        true
+    }
+}
+
+/// The bindings for an action set, copied into every matching input map.
+#[derive(Resource)]
+pub struct Bindings<A: Rebindable>(pub InputMap<A>);
+
+impl<A: Rebindable> Default for Bindings<A> {
+    fn default() -> Self {
+        Self(A::default_input_map())
+    }
+}
+
+impl<A: Rebindable> Bindings<A> {
+    /// The saved bindings, or the defaults if none were saved or they can no
+    /// longer be read.
+    fn load() -> Self {
+        storage::load(A::STORAGE_KEY)
+            .and_then(|text| match ron::from_str(&text) {
+                Ok(input_map) => Some(Self(input_map)),
+                Err(error) => {
+                    warn!("Ignoring saved {}: {}", A::STORAGE_KEY, error);
+                    None
+                }
+            })
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        match ron::to_string(&self.0) {
+            Ok(text) => storage::save(A::STORAGE_KEY, &text),
+            Err(error) => warn!("Could not save {}: {}", A::STORAGE_KEY, error),
+        }
+    }
+
+    fn keys(&self, action: A) -> Vec<KeyCode> {
+        self.0
+            .get(action)
+            .iter()
+            .filter_map(|input| match input {
+                UserInput::Single(InputKind::Keyboard(key)) => Some(*key),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Binds `key` to `action` in place of its other keys, leaving its
+    /// gamepad and mouse bindings alone. The key is taken from any other
+    /// action it was bound to.
+    fn rebind(&mut self, action: A, key: KeyCode) {
+        A::variants().for_each(|other| {
+            self.0.remove(other, key);
+        });
+        self.keys(action).into_iter().for_each(|old| {
+            self.0.remove(action, old);
+        });
+        self.0.insert(key, action);
+    }
+}
+
+/// The keyboard keys used by `input`, however it combines them.
+fn keyboard_keys(input: &UserInput) -> Vec<KeyCode> {
+    let kinds = match input {
+        UserInput::Single(kind) => vec![*kind],
+        UserInput::Chord(kinds) => kinds.iter().copied().collect(),
+        UserInput::VirtualDPad(dpad) => {
+            vec![dpad.up, dpad.down, dpad.left, dpad.right]
+        }
+        UserInput::VirtualAxis(axis) => vec![axis.negative, axis.positive],
+    };
+    kinds
+        .into_iter()
+        .filter_map(|kind| match kind {
+            InputKind::Keyboard(key) => Some(key),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Every key bound in any action set, with the set and action using it, so
+/// that one key press cannot fire actions of two sets at once.
+#[derive(Resource, Default)]
+pub struct ReservedKeys(HashMap<KeyCode, (&'static str, String)>);
+
+impl ReservedKeys {
+    /// Records the keys of `input_map` as the only ones held by `set`.
+    pub fn reserve<A: Actionlike + Debug>(
+        &mut self,
+        set: &'static str,
+        input_map: &InputMap<A>,
+    ) {
+        self.0.retain(|_, (owner, _)| *owner != set);
+        A::variants().for_each(|action| {
+            input_map
+                .get(action.clone())
+                .iter()
+                .flat_map(keyboard_keys)
+                .for_each(|key| {
+                    self.0.insert(key, (set, format!("{:?}", action)));
+                })
+        });
+    }
+
+    /// The action of a set other than `set` already bound to `key`.
+    fn clash(&self, set: &'static str, key: KeyCode) -> Option<&str> {
+        self.0
+            .get(&key)
+            .filter(|(owner, _)| *owner != set)
+            .map(|(_, action)| action.as_str())
+    }
+}
+
+/// The settings screen, present only while it is open.
+#[derive(Component)]
+struct SettingsScreen;
+
+/// The list of bindings on the settings screen.
+#[derive(Component)]
+struct BindingList;
+
+#[derive(Component)]
+struct ResetButton;
+
+/// Explains why the last key pressed for a rebind was refused.
+#[derive(Component)]
+struct ClashText;
+
+#[derive(Resource, Default)]
+struct ClashMessage(String);
+
+/// A button which rebinds the keys of an action when clicked.
+#[derive(Component)]
+struct RebindButton<A: Rebindable>(A);
+
+/// The rebind button waiting for a key press, if any.
+#[derive(Resource, Default)]
+struct Listening(Option<Entity>);
+
+#[derive(Resource)]
+struct SettingsFont(Handle<Font>);
+
+impl FromWorld for SettingsFont {
+    fn from_world(world: &mut World) -> Self {
+        Self(
+            world
+                .resource::<AssetServer>()
+                .load("fonts/FiraSans-Regular.ttf"),
+        )
+    }
+}
+
+fn text_style(font: &SettingsFont) -> TextStyle {
+    TextStyle {
+        font: font.0.clone(),
+        font_size: 24.0,
+        color: Color::WHITE,
+    }
+}
+
+fn button_bundle() -> ButtonBundle {
+    ButtonBundle {
+        style: Style {
+            padding: UiRect::all(Val::Px(6.0)),
+            margin: UiRect::all(Val::Px(4.0)),
+            min_size: Size::new(Val::Px(160.0), Val::Auto),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        background_color: Color::hsl(0.0, 0.0, 0.25).into(),
+        ..default()
+    }
+}
+
+fn toggle_settings(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<SettingsScreen>>,
+    mut listening: ResMut<Listening>,
+    mut clash_message: ResMut<ClashMessage>,
+    keys: Res<Input<KeyCode>>,
+    font: Res<SettingsFont>,
+) {
+    if !keys.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    clash_message.0.clear();
+    if listening.0.take().is_some() {
+        return;
+    }
+    if let Ok(screen) = screen_query.get_single() {
+        commands.entity(screen).despawn_recursive();
+        return;
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                ..default()
+            },
+            SettingsScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Controls",
+                TextStyle {
+                    font_size: 36.0,
+                    ..text_style(&font)
+                },
+            ));
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        margin: UiRect::all(Val::Px(12.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                BindingList,
+            ));
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 18.0,
+                        color: Color::hsl(0.0, 0.8, 0.7),
+                        ..text_style(&font)
+                    },
+                ),
+                ClashText,
+            ));
+            parent.spawn((button_bundle(), ResetButton)).with_children(
+                |parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Reset to defaults",
+                        text_style(&font),
+                    ));
+                },
+            );
+            parent.spawn(TextBundle::from_section(
+                "Click a binding, then press a key. Esc to close.",
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::hsl(0.0, 0.0, 0.8),
+                    ..text_style(&font)
+                },
+            ));
+        });
+}
+
+fn spawn_binding_rows<A: Rebindable>(
+    mut commands: Commands,
+    list_query: Query<Entity, Added<BindingList>>,
+    font: Res<SettingsFont>,
+) {
+    list_query.iter().for_each(|list| {
+        commands.entity(list).with_children(|parent| {
+            A::variants().filter(|action| action.rebindable()).for_each(
+                |action| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                justify_content: JustifyContent::SpaceBetween,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                format!("{:?}", action),
+                                text_style(&font),
+                            ));
+                            parent
+                                .spawn((button_bundle(), RebindButton(action)))
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        "",
+                                        text_style(&font),
+                                    ));
+                                });
+                        });
+                },
+            );
+        });
+    });
+}
+
+#[allow(clippy::type_complexity)]
+fn start_listening<A: Rebindable>(
+    button_query: Query<
+        (Entity, &Interaction),
+        (Changed<Interaction>, With<RebindButton<A>>),
+    >,
+    mut listening: ResMut<Listening>,
+) {
+    button_query.iter().for_each(|(entity, interaction)| {
+        if *interaction == Interaction::Clicked {
+            listening.0 = Some(entity);
+        }
+    });
+}
+
+/// Binds the next key pressed to the listening button's action, unless an
+/// action of another set already uses it.
+fn capture_key<A: Rebindable>(
+    button_query: Query<&RebindButton<A>>,
+    mut listening: ResMut<Listening>,
+    mut bindings: ResMut<Bindings<A>>,
+    mut clash_message: ResMut<ClashMessage>,
+    reserved: Res<ReservedKeys>,
+    keys: Res<Input<KeyCode>>,
+) {
+    let Some(RebindButton(action)) =
+        listening.0.and_then(|entity| button_query.get(entity).ok())
+    else {
+        return;
+    };
+    if let Some(&key) =
+        keys.get_just_pressed().find(|&&key| key != KeyCode::Escape)
+    {
+        if let Some(other) = reserved.clash(A::STORAGE_KEY, key) {
+            clash_message.0 =
+                format!("{:?} is already bound to {}", key, other);
+        } else {
+            bindings.rebind(*action, key);
+            clash_message.0.clear();
+            listening.0 = None;
+        }
+    }
+}
+
+fn show_clash(
+    mut text_query: Query<&mut Text, With<ClashText>>,
+    clash_message: Res<ClashMessage>,
+) {
+    text_query.iter_mut().for_each(|mut text| {
+        if text.sections[0].value != clash_message.0 {
+            text.sections[0].value = clash_message.0.clone();
+        }
+    })
+}
+
+fn reserve_keys<A: Rebindable>(
+    mut reserved: ResMut<ReservedKeys>,
+    bindings: Res<Bindings<A>>,
+) {
+    if bindings.is_changed() {
+        reserved.reserve(A::STORAGE_KEY, &bindings.0);
+    }
+}
+
+fn reset_bindings<A: Rebindable>(
+    button_query: Query<
+        &Interaction,
+        (Changed<Interaction>, With<ResetButton>),
+    >,
+    mut bindings: ResMut<Bindings<A>>,
+) {
+    if button_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Clicked)
+    {
+        *bindings = Bindings::default();
+    }
+}
+
+fn label_rebind_buttons<A: Rebindable>(
+    button_query: Query<(Entity, &RebindButton<A>, &Children)>,
+    mut text_query: Query<&mut Text>,
+    listening: Res<Listening>,
+    bindings: Res<Bindings<A>>,
+) {
+    button_query.iter().for_each(|(entity, button, children)| {
+        let label = if listening.0 == Some(entity) {
+            "Press a key...".to_string()
+        } else {
+            let keys = bindings.keys(button.0);
+            if keys.is_empty() {
+                "Unbound".to_string()
+            } else {
+                keys.iter()
+                    .map(|key| format!("{:?}", key))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        };
+        children.iter().for_each(|&child| {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                text.sections[0].value = label.clone();
+            }
+        });
+    });
+}
+
+/// Copies changed bindings into every input map for the action set, and
+/// saves them.
+fn apply_bindings<A: Rebindable>(
+    mut input_map_query: Query<&mut InputMap<A>>,
+    bindings: Res<Bindings<A>>,
+) {
+    if bindings.is_changed() && !bindings.is_added() {
+        input_map_query.iter_mut().for_each(|mut input_map| {
+            *input_map = bindings.0.clone();
+        });
+        bindings.save();
+    }
+}
+
+/// Loads the saved bindings for an action set, lists them on the settings
+/// screen for rebinding, and saves them whenever they change.
+pub struct BindingsPlugin<A: Rebindable>(PhantomData<A>);
+
+impl<A: Rebindable> Default for BindingsPlugin<A> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<A: Rebindable> Plugin for BindingsPlugin<A> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Bindings::<A>::load())
+            .init_resource::<ReservedKeys>()
+            .add_system(reserve_keys::<A>)
+            .add_system(spawn_binding_rows::<A>)
+            .add_system(start_listening::<A>)
+            .add_system(capture_key::<A>)
+            .add_system(reset_bindings::<A>)
+            .add_system(label_rebind_buttons::<A>)
+            .add_system(apply_bindings::<A>);
+    }
+}
+
+/// The settings screen, opened and closed with Escape.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Listening>()
+            .init_resource::<ClashMessage>()
+            .init_resource::<SettingsFont>()
+            .add_system(toggle_settings)
+            .add_system(show_clash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use leafwing_input_manager::axislike::VirtualAxis;
+
+    use super::*;
+
+    #[derive(Actionlike, Clone, Debug)]
+    enum TestAction {
+        Jump,
+        Turn,
+    }
+
+    #[test]
+    fn clashes_only_with_other_sets() {
+        let mut input_map = InputMap::default();
+        input_map.insert(KeyCode::Space, TestAction::Jump);
+        input_map.insert(
+            VirtualAxis {
+                negative: KeyCode::Q.into(),
+                positive: KeyCode::E.into(),
+            },
+            TestAction::Turn,
+        );
+        let mut reserved = ReservedKeys::default();
+        reserved.reserve("test", &input_map);
+
+        assert_eq!(reserved.clash("other", KeyCode::Space), Some("Jump"));
+        assert_eq!(reserved.clash("other", KeyCode::E), Some("Turn"));
+        assert_eq!(reserved.clash("other", KeyCode::W), None);
+        assert_eq!(reserved.clash("test", KeyCode::Space), None);
+    }
+
+    #[test]
+    fn reserving_again_frees_old_keys() {
+        let mut input_map = InputMap::default();
+        input_map.insert(KeyCode::Space, TestAction::Jump);
+        let mut reserved = ReservedKeys::default();
+        reserved.reserve("test", &input_map);
+
+        let mut input_map = InputMap::default();
+        input_map.insert(KeyCode::J, TestAction::Jump);
+        reserved.reserve("test", &input_map);
+
+        assert_eq!(reserved.clash("other", KeyCode::Space), None);
+        assert_eq!(reserved.clash("other", KeyCode::J), Some("Jump"));
+    }
+}
@@ -1,11 +1,28 @@
-use std::marker::PhantomData;
-
 use bevy::prelude::shape;
 use bevy::prelude::*;
+use iyes_loopless::prelude::*;
 
+use crate::archetype::SheepArchetype;
 use crate::barrier::Barrier;
-use crate::common::MaxSpeed;
-use crate::player::PlayerTag;
+use crate::common::{ground_position, MaxSpeed, Presence, RoundScoped, Threat};
+use crate::dog::{DogTag, MoveDogLabel};
+use crate::flocking::{
+    ActiveFlockingConfig, FlockingConfig, FlockingConfigLabel, PanicConfig,
+    WanderConfig,
+};
+use crate::grid::SpatialGrid;
+use crate::player::{
+    Crook, CrookLabel, MovePlayerLabel, PlayerTag, Shout, ShoutLabel,
+    SprintLabel, CROOK_LENGTH, CROOK_SPEED, PLAYER_RADIUS, SHOUT_RADIUS,
+};
+use crate::simulation::{
+    step_seconds, Interpolated, SIMULATION_SUBSTAGE, SIMULATION_TIMESTEP,
+};
+use crate::steering::{
+    Alignment, Avoidance, Coalescence, RegisterSteering, Steering,
+    SteeringForce, SteeringLabel,
+};
+use crate::wolf::{MoveWolvesLabel, WolfTag};
 
 #[derive(Component)]
 struct Speed(Vec2);
@@ -16,61 +33,100 @@ impl Speed {
     }
 }
 
+/// The speed a shout sends a sheep off at, before its top speed caps it.
+const SHOUT_IMPULSE: f32 = 15.0;
+
+/// How much faster than the shepherd a hooked sheep may go, so that it
+/// keeps up despite its drag.
+const HOOKED_CATCH_UP: f32 = 1.2;
+
+/// The largest turn, in radians, a wandering sheep makes when it picks a new
+/// heading.
+const WANDER_TURN: f32 = 1.5;
+/// How close a wandering sheep comes to a barrier before turning away.
+const WANDER_CLEARANCE: f32 = 3.0;
+
+/// Idle ambling: a heading held for a while, or a pause to graze, chosen
+/// afresh whenever the timer runs out.
 #[derive(Component)]
-struct Avoidance<C: Component> {
+struct Wander {
     strength: f32,
-    range: f32,
-    influences: Vec<Vec2>,
-    component: PhantomData<C>,
+    interval: f32,
+    graze_chance: f32,
+    /// The current heading, or zero while grazing.
+    heading: Vec2,
+    remaining: f32,
+    rng: fastrand::Rng,
 }
 
-impl<C: Component> Avoidance<C> {
-    fn new(strength: f32, range: f32) -> Self {
+impl Wander {
+    fn new(config: &WanderConfig, rng: fastrand::Rng) -> Self {
         Self {
-            strength,
-            range,
-            influences: Vec::new(),
-            component: PhantomData,
+            strength: config.strength,
+            interval: config.interval,
+            graze_chance: config.graze_chance,
+            heading: Vec2::ZERO,
+            remaining: 0_f32,
+            rng,
         }
     }
-}
 
-#[derive(Component)]
-struct Coalescence<C: Component> {
-    strength: f32,
-    range: f32,
-    influences: Vec<Vec2>,
-    component: PhantomData<C>,
+    fn configure(&mut self, config: &WanderConfig) {
+        self.strength = config.strength;
+        self.interval = config.interval;
+        self.graze_chance = config.graze_chance;
+    }
+
+    fn choose(&mut self) {
+        self.remaining = self.interval * (0.5 + self.rng.f32());
+        self.heading = if self.rng.f32() < self.graze_chance {
+            Vec2::ZERO
+        } else if self.heading == Vec2::ZERO {
+            Vec2::from_angle(self.rng.f32() * std::f32::consts::TAU)
+        } else {
+            Vec2::from_angle((self.rng.f32() * 2_f32 - 1_f32) * WANDER_TURN)
+                .rotate(self.heading)
+        };
+    }
 }
 
-impl<C: Component> Coalescence<C> {
-    fn new(strength: f32, range: f32) -> Self {
-        Self {
-            strength,
-            range,
-            influences: Vec::new(),
-            component: PhantomData,
-        }
+impl Steering for Wander {
+    fn take(&mut self) -> Vec2 {
+        self.heading * self.strength
     }
 }
 
+/// How frightened a sheep is, from calm at zero to stampeding at one.
+/// Panic raises the sheep's top speed and loosens its hold on the flock.
 #[derive(Component)]
-struct Alignment<C: Component> {
-    strength: f32,
-    range: f32,
-    influences: Vec<Vec2>,
-    component: PhantomData<C>,
+struct Panic {
+    level: f32,
+    /// Panic from threats gathered this step.
+    fright: f32,
+    /// The highest panic among the sheep's neighbours this step.
+    exposure: f32,
+    config: PanicConfig,
+    calm_speed: f32,
+    calm_coalescence: f32,
 }
 
-impl<C: Component> Alignment<C> {
-    fn new(strength: f32, range: f32) -> Self {
+impl Panic {
+    fn new(config: &FlockingConfig) -> Self {
         Self {
-            strength,
-            range,
-            influences: Vec::new(),
-            component: PhantomData,
+            level: 0_f32,
+            fright: 0_f32,
+            exposure: 0_f32,
+            config: config.panic,
+            calm_speed: config.max_speed,
+            calm_coalescence: config.sheep_coalescence.strength,
         }
     }
+
+    fn configure(&mut self, config: &FlockingConfig) {
+        self.config = config.panic;
+        self.calm_speed = config.max_speed;
+        self.calm_coalescence = config.sheep_coalescence.strength;
+    }
 }
 
 #[derive(Component)]
@@ -79,48 +135,74 @@ pub struct SheepTag;
 #[derive(Bundle)]
 pub struct SheepBundle {
     tag: SheepTag,
+    archetype: SheepArchetype,
+    round: RoundScoped,
     #[bundle]
-    material_mesh: PbrBundle,
+    transform: TransformBundle,
+    interpolated: Interpolated,
     speed: MaxSpeed,
     momentum: Speed,
+    steering: SteeringForce,
     player_avoidance: Avoidance<PlayerTag>,
+    wolf_avoidance: Avoidance<WolfTag>,
+    dog_avoidance: Avoidance<DogTag>,
     barrier_avoidance: Avoidance<Barrier>,
     sheep_avoidance: Avoidance<SheepTag>,
     sheep_coalescence: Coalescence<SheepTag>,
     sheep_alignment: Alignment<SheepTag>,
+    wander: Wander,
+    panic: Panic,
 }
 
 impl SheepBundle {
     fn new(
-        mesh: Handle<Mesh>,
-        material: Handle<StandardMaterial>,
         position: Vec2,
+        archetype: SheepArchetype,
+        config: &FlockingConfig,
+        rng: &mut fastrand::Rng,
     ) -> Self {
+        let transform = Transform::from_xyz(position.x, 0_f32, position.y);
+        let config = &archetype.adjust(config);
         Self {
             tag: SheepTag,
-            material_mesh: PbrBundle {
-                mesh,
-                material,
-                transform: Transform::from_xyz(position.x, 0_f32, position.y),
-                ..default()
-            },
-            speed: MaxSpeed::new(5.0),
+            archetype,
+            round: RoundScoped,
+            transform: TransformBundle::from_transform(transform),
+            interpolated: Interpolated::new(transform),
+            speed: MaxSpeed::new(config.max_speed),
             momentum: Speed::new(),
-            player_avoidance: Avoidance::new(100.0, 10_f32),
-            barrier_avoidance: Avoidance::new(100.0, 5_f32),
-            sheep_avoidance: Avoidance::new(10.0, 10_f32),
-            sheep_coalescence: Coalescence::new(5.0, 10_f32),
-            sheep_alignment: Alignment::new(1.0, 10_f32),
+            steering: SteeringForce::default(),
+            player_avoidance: Avoidance::new(&config.player_avoidance),
+            wolf_avoidance: Avoidance::new(&config.wolf_avoidance),
+            dog_avoidance: Avoidance::new(&config.dog_avoidance),
+            barrier_avoidance: Avoidance::new(&config.barrier_avoidance),
+            sheep_avoidance: Avoidance::new(&config.sheep_avoidance),
+            sheep_coalescence: Coalescence::new(&config.sheep_coalescence),
+            sheep_alignment: Alignment::new(&config.sheep_alignment),
+            wander: Wander::new(&config.wander, rng.fork()),
+            panic: Panic::new(config),
         }
     }
 
     pub fn spawn(
         commands: &mut Commands,
-        mesh_assets: &mut ResMut<Assets<Mesh>>,
-        standard_material_assets: &mut ResMut<Assets<StandardMaterial>>,
         position: Vec2,
+        archetype: SheepArchetype,
+        config: &FlockingConfig,
+        rng: &mut fastrand::Rng,
     ) {
-        commands.spawn(SheepBundle::new(
+        commands.spawn(SheepBundle::new(position, archetype, config, rng));
+    }
+}
+
+fn add_sheep_meshes(
+    mut commands: Commands,
+    sheep_query: Query<(Entity, &SheepArchetype), Added<SheepTag>>,
+    mut mesh_assets: ResMut<Assets<Mesh>>,
+    mut standard_material_assets: ResMut<Assets<StandardMaterial>>,
+) {
+    sheep_query.iter().for_each(|(entity, archetype)| {
+        commands.entity(entity).insert((
             mesh_assets.add(Mesh::from(shape::Box {
                 min_x: -0.5,
                 max_x: 0.5,
@@ -130,110 +212,198 @@ impl SheepBundle {
                 max_z: 0.25,
             })),
             standard_material_assets
-                .add(StandardMaterial::from(Color::ANTIQUE_WHITE)),
-            position,
+                .add(StandardMaterial::from(archetype.tint())),
+            VisibilityBundle::default(),
         ));
-    }
+    });
 }
 
-fn player_influence(
-    mut sheep_query: Query<
-        (&mut Avoidance<PlayerTag>, &Transform),
-        With<SheepTag>,
+/// Pushes sheep away from every entity with the component `C` in range.
+#[allow(clippy::type_complexity)]
+fn avoid_entities<C: Component>(
+    mut sheep_query: Query<(&mut Avoidance<C>, &Transform), With<SheepTag>>,
+    source_query: Query<
+        (&Transform, Option<&Presence>),
+        (With<C>, Without<SheepTag>),
     >,
-    player_query: Query<&Transform, (With<PlayerTag>, Without<SheepTag>)>,
 ) {
     sheep_query
         .iter_mut()
         .for_each(|(mut avoidance, sheep_transform)| {
+            source_query
+                .iter()
+                .for_each(|(source_transform, presence)| {
+                    let seperation = ground_position(sheep_transform)
+                        - ground_position(source_transform);
+                    let range = avoidance.range
+                        * presence.map_or(1_f32, |presence| presence.0);
+                    if seperation.length() < range {
+                        avoidance
+                            .push(seperation / seperation.length_squared());
+                    }
+                })
+        })
+}
+
+#[derive(SystemLabel)]
+pub struct SheepGridLabel;
+
+fn build_sheep_grid(
+    mut grid: ResMut<SpatialGrid>,
+    sheep_query: Query<(Entity, &Transform, &Speed, &Panic), With<SheepTag>>,
+) {
+    grid.clear();
+    sheep_query
+        .iter()
+        .for_each(|(entity, transform, speed, panic)| {
+            grid.insert(
+                entity,
+                ground_position(transform),
+                speed.0,
+                panic.level,
+            )
+        });
+}
+
+#[derive(SystemLabel)]
+struct PlayerContactLabel;
+
+fn player_contact(
+    mut sheep_query: Query<(&mut Speed, &Transform), With<SheepTag>>,
+    player_query: Query<&Transform, (With<PlayerTag>, Without<SheepTag>)>,
+    timesteps: Res<FixedTimesteps>,
+) {
+    let delta_seconds = step_seconds(&timesteps);
+    sheep_query
+        .iter_mut()
+        .for_each(|(mut speed, sheep_transform)| {
             player_query.iter().for_each(|player_transform| {
-                let seperation = Vec2::new(
-                    sheep_transform.translation.x,
-                    sheep_transform.translation.z,
-                ) - Vec2::new(
-                    player_transform.translation.x,
-                    player_transform.translation.z,
-                );
-                if seperation.length() < avoidance.range {
-                    avoidance
-                        .influences
-                        .push(seperation / seperation.length_squared());
+                let seperation = ground_position(sheep_transform)
+                    - ground_position(player_transform);
+                let overlap =
+                    SHEEP_RADIUS + PLAYER_RADIUS - seperation.length();
+                if overlap > 0_f32 {
+                    speed.0 += seperation.normalize_or_zero() * overlap
+                        / delta_seconds;
                 }
             })
         })
 }
 
+#[derive(SystemLabel)]
+struct ShoutScareLabel;
+
+/// Sends sheep in earshot of a shout running from the shepherd, however far
+/// they are beyond the reach of their usual avoidance.
+fn shout_scare(
+    mut sheep_query: Query<(&mut Speed, &Transform), With<SheepTag>>,
+    player_query: Query<(&Transform, &Shout), Without<SheepTag>>,
+) {
+    player_query
+        .iter()
+        .filter(|(_, shout)| shout.sounding)
+        .for_each(|(player_transform, _)| {
+            let origin = ground_position(player_transform);
+            sheep_query
+                .iter_mut()
+                .for_each(|(mut speed, sheep_transform)| {
+                    let seperation = ground_position(sheep_transform) - origin;
+                    if seperation.length() < SHOUT_RADIUS {
+                        speed.0 +=
+                            seperation.normalize_or_zero() * SHOUT_IMPULSE;
+                    }
+                })
+        })
+}
+
+/// Drags each sheep hooked on a crook along behind the shepherd, in place of
+/// its own steering.
+fn drag_hooked_sheep(
+    mut sheep_query: Query<
+        (&mut Speed, &mut MaxSpeed, &mut SteeringForce, &Transform),
+        With<SheepTag>,
+    >,
+    player_query: Query<(&Transform, &Crook), Without<SheepTag>>,
+    timesteps: Res<FixedTimesteps>,
+) {
+    let delta_seconds = step_seconds(&timesteps);
+    player_query
+        .iter()
+        .filter_map(|(player_transform, crook)| {
+            Some((player_transform, crook.held?))
+        })
+        .for_each(|(player_transform, held)| {
+            let Ok((mut speed, mut max_speed, mut steering, sheep_transform)) =
+                sheep_query.get_mut(held)
+            else {
+                return;
+            };
+            let seperation = ground_position(player_transform)
+                - ground_position(sheep_transform);
+            let slack = seperation.length() - CROOK_LENGTH;
+            max_speed.0 = CROOK_SPEED * HOOKED_CATCH_UP;
+            speed.0 = seperation.normalize_or_zero()
+                * (slack / delta_seconds).clamp(0_f32, max_speed.0);
+            steering.0 = Vec2::ZERO;
+        });
+}
+
 #[allow(clippy::type_complexity)]
 fn sheep_influences(
     mut sheep_query: Query<
         (
+            Entity,
             &mut Avoidance<SheepTag>,
             &mut Coalescence<SheepTag>,
             &mut Alignment<SheepTag>,
+            &mut Panic,
             &Transform,
-            &Speed,
         ),
         With<SheepTag>,
     >,
+    grid: Res<SpatialGrid>,
 ) {
-    let mut combinations = sheep_query.iter_combinations_mut::<2>();
-    while let Some(
-        [(
-            mut sheep_a_avoidance,
-            mut sheep_a_coalescence,
-            mut sheep_a_alignment,
-            sheep_a_transform,
-            sheep_a_speed,
-        ), (
-            mut sheep_b_avoidance,
-            mut sheep_b_coalescence,
-            mut sheep_b_alignment,
-            sheep_b_transform,
-            sheep_b_speed,
-        )],
-    ) = combinations.fetch_next()
-    {
-        let seperation = Vec2::new(
-            sheep_a_transform.translation.x,
-            sheep_a_transform.translation.z,
-        ) - Vec2::new(
-            sheep_b_transform.translation.x,
-            sheep_b_transform.translation.z,
-        );
-        let seperation_length_squared = seperation.length_squared();
-        let seperation_length = seperation_length_squared.sqrt();
-
-        if seperation_length_squared < sheep_a_avoidance.range.powi(2) {
-            sheep_a_avoidance
-                .influences
-                .push(seperation / seperation_length_squared)
-        }
-        if seperation_length_squared < sheep_b_avoidance.range.powi(2) {
-            sheep_b_avoidance
-                .influences
-                .push(-seperation / seperation_length_squared)
-        }
-        if seperation_length_squared < sheep_a_coalescence.range.powi(2) {
-            sheep_a_coalescence
-                .influences
-                .push(-seperation / seperation_length)
-        }
-        if seperation_length_squared < sheep_b_coalescence.range.powi(2) {
-            sheep_b_coalescence
-                .influences
-                .push(seperation / seperation_length)
-        }
-        if seperation_length_squared < sheep_a_alignment.range.powi(2) {
-            sheep_a_alignment
-                .influences
-                .push(sheep_b_speed.0 / seperation_length)
-        }
-        if seperation_length_squared < sheep_b_alignment.range.powi(2) {
-            sheep_b_alignment
-                .influences
-                .push(sheep_a_speed.0 / seperation_length)
-        }
-    }
+    sheep_query.iter_mut().for_each(
+        |(
+            entity,
+            mut sheep_avoidance,
+            mut sheep_coalescence,
+            mut sheep_alignment,
+            mut panic,
+            transform,
+        )| {
+            let position = ground_position(transform);
+            let range = sheep_avoidance
+                .range
+                .max(sheep_coalescence.range)
+                .max(sheep_alignment.range);
+            grid.neighbours(position, range)
+                .filter(|neighbour| neighbour.entity != entity)
+                .for_each(|neighbour| {
+                    panic.exposure = panic.exposure.max(neighbour.panic);
+
+                    let seperation = position - neighbour.position;
+                    let seperation_length_squared = seperation.length_squared();
+                    let seperation_length = seperation_length_squared.sqrt();
+
+                    if seperation_length_squared < sheep_avoidance.range.powi(2)
+                    {
+                        sheep_avoidance
+                            .push(seperation / seperation_length_squared)
+                    }
+                    if seperation_length_squared
+                        < sheep_coalescence.range.powi(2)
+                    {
+                        sheep_coalescence.push(-seperation / seperation_length)
+                    }
+                    if seperation_length_squared < sheep_alignment.range.powi(2)
+                    {
+                        sheep_alignment
+                            .push(neighbour.velocity / seperation_length)
+                    }
+                })
+        },
+    )
 }
 
 fn barrier_influence(
@@ -247,94 +417,206 @@ fn barrier_influence(
         .iter_mut()
         .for_each(|(mut avoidance, sheep_transform)| {
             linear_barrier_query.iter().for_each(|barrier| {
-                let sheep_position = Vec2::new(
-                    sheep_transform.translation.x,
-                    sheep_transform.translation.z,
-                );
+                let sheep_position = ground_position(sheep_transform);
                 let seperation =
                     sheep_position - barrier.projected_point(sheep_position);
                 if seperation.length() < avoidance.range {
-                    avoidance
-                        .influences
-                        .push(seperation / seperation.length_squared());
+                    avoidance.push(seperation / seperation.length_squared());
                 }
             })
         })
 }
 
+fn wander_influence(
+    mut sheep_query: Query<(&mut Wander, &Transform), With<SheepTag>>,
+    barrier_query: Query<&Barrier, Without<SheepTag>>,
+    timesteps: Res<FixedTimesteps>,
+) {
+    let delta_seconds = step_seconds(&timesteps);
+    sheep_query
+        .iter_mut()
+        .for_each(|(mut wander, sheep_transform)| {
+            wander.remaining -= delta_seconds;
+            if wander.remaining <= 0_f32 {
+                wander.choose();
+            }
+
+            // Turn away from barriers rather than leaning on them.
+            let sheep_position = ground_position(sheep_transform);
+            barrier_query.iter().for_each(|barrier| {
+                let seperation =
+                    sheep_position - barrier.projected_point(sheep_position);
+                let normal = seperation.normalize_or_zero();
+                let approach = wander.heading.dot(normal);
+                if seperation.length() < WANDER_CLEARANCE && approach < 0_f32 {
+                    wander.heading -= 2_f32 * approach * normal;
+                }
+            });
+        })
+}
+
 #[derive(SystemLabel)]
-struct MoveSheepLabel;
+struct FrightLabel;
 
+/// Frightens sheep with threats rushing towards them, the more so the
+/// faster and closer they come.
 #[allow(clippy::type_complexity)]
-fn move_sheep(
+fn threat_panic(
+    mut sheep_query: Query<(&mut Panic, &Transform), With<SheepTag>>,
+    threat_query: Query<
+        (&Transform, &Interpolated),
+        (With<Threat>, Without<SheepTag>),
+    >,
+    timesteps: Res<FixedTimesteps>,
+) {
+    let delta_seconds = step_seconds(&timesteps);
+    sheep_query
+        .iter_mut()
+        .for_each(|(mut panic, sheep_transform)| {
+            threat_query
+                .iter()
+                .for_each(|(threat_transform, interpolated)| {
+                    let seperation = ground_position(sheep_transform)
+                        - ground_position(threat_transform);
+                    let distance = seperation.length();
+                    if distance >= panic.config.range {
+                        return;
+                    }
+                    let displacement =
+                        interpolated.step_displacement(threat_transform);
+                    let closing_speed =
+                        Vec2::new(displacement.x, displacement.z)
+                            .dot(seperation.normalize_or_zero())
+                            / delta_seconds;
+                    if closing_speed > 0_f32 {
+                        panic.fright += closing_speed
+                            * panic.config.sensitivity
+                            * (1_f32 - distance / panic.config.range)
+                            * delta_seconds;
+                    }
+                })
+        })
+}
+
+#[derive(SystemLabel)]
+struct PanicLabel;
+
+/// Raises panic from fright and frightened neighbours, lets it ebb away, and
+/// applies it to the sheep's speed and coalescence.
+fn update_panic(
+    mut sheep_query: Query<
+        (&mut Panic, &mut MaxSpeed, &mut Coalescence<SheepTag>),
+        With<SheepTag>,
+    >,
+    timesteps: Res<FixedTimesteps>,
+) {
+    let delta_seconds = step_seconds(&timesteps);
+    sheep_query.iter_mut().for_each(
+        |(mut panic, mut max_speed, mut sheep_coalescence)| {
+            let contagion = (panic.exposure - panic.level).max(0_f32)
+                * panic.config.contagion
+                * delta_seconds;
+            panic.level = (panic.level + panic.fright + contagion
+                - panic.config.calm_rate * delta_seconds)
+                .clamp(0_f32, 1_f32);
+            panic.fright = 0_f32;
+            panic.exposure = 0_f32;
+
+            max_speed.0 = panic.calm_speed
+                * (1_f32 + panic.config.speed_boost * panic.level);
+            sheep_coalescence.strength = panic.calm_coalescence
+                * (1_f32 + (panic.config.coalescence - 1_f32) * panic.level);
+        },
+    )
+}
+
+#[allow(clippy::type_complexity)]
+fn apply_flocking_config(
     mut sheep_query: Query<
         (
-            &mut Transform,
+            &mut MaxSpeed,
             &mut Avoidance<PlayerTag>,
+            &mut Avoidance<WolfTag>,
+            &mut Avoidance<DogTag>,
             &mut Avoidance<Barrier>,
             &mut Avoidance<SheepTag>,
             &mut Coalescence<SheepTag>,
             &mut Alignment<SheepTag>,
-            &mut Speed,
-            &MaxSpeed,
+            &mut Wander,
+            &mut Panic,
+            &SheepArchetype,
         ),
         With<SheepTag>,
     >,
-    time: Res<Time>,
+    active_config: Res<ActiveFlockingConfig>,
 ) {
+    if !active_config.is_changed() {
+        return;
+    }
     sheep_query.iter_mut().for_each(
         |(
-            mut transform,
+            mut max_speed,
             mut player_avoidance,
+            mut wolf_avoidance,
+            mut dog_avoidance,
             mut barrier_avoidance,
             mut sheep_avoidance,
             mut sheep_coalescence,
             mut sheep_alignment,
-            mut speed,
-            max_speed,
+            mut wander,
+            mut panic,
+            archetype,
         )| {
-            let player_avoidance_influence =
-                player_avoidance.influences.iter().sum::<Vec2>();
-            player_avoidance.influences.clear();
-            let barrier_avoidance_influence =
-                barrier_avoidance.influences.iter().sum::<Vec2>();
-            barrier_avoidance.influences.clear();
-            let sheep_avoidance_influence =
-                sheep_avoidance.influences.iter().sum::<Vec2>();
-            sheep_avoidance.influences.clear();
-            let sheep_coalescence_influence =
-                sheep_coalescence.influences.iter().sum::<Vec2>();
-            sheep_coalescence.influences.clear();
-            let sheep_alignment_influence =
-                if !sheep_alignment.influences.is_empty() {
-                    sheep_alignment.influences.iter().sum::<Vec2>()
-                        / sheep_alignment.influences.len() as f32
-                } else {
-                    Vec2::ZERO
-                };
-            sheep_alignment.influences.clear();
-            speed.0 = (speed.0
-                + player_avoidance_influence
-                    * player_avoidance.strength
-                    * time.delta_seconds()
-                + barrier_avoidance_influence
-                    * barrier_avoidance.strength
-                    * time.delta_seconds()
-                + sheep_avoidance_influence
-                    * sheep_avoidance.strength
-                    * time.delta_seconds()
-                + sheep_coalescence_influence
-                    * sheep_coalescence.strength
-                    * time.delta_seconds()
-                + sheep_alignment_influence
-                    * sheep_alignment.strength
-                    * time.delta_seconds()
-                - 0.8 * speed.0 * time.delta_seconds())
-            .clamp_length_max(max_speed.0);
+            let config = &archetype.adjust(&active_config.config);
+            max_speed.0 = config.max_speed;
+            player_avoidance.configure(&config.player_avoidance);
+            wolf_avoidance.configure(&config.wolf_avoidance);
+            dog_avoidance.configure(&config.dog_avoidance);
+            barrier_avoidance.configure(&config.barrier_avoidance);
+            sheep_avoidance.configure(&config.sheep_avoidance);
+            sheep_coalescence.configure(&config.sheep_coalescence);
+            sheep_alignment.configure(&config.sheep_alignment);
+            wander.configure(&config.wander);
+            panic.configure(config);
+        },
+    )
+}
+
+#[derive(SystemLabel)]
+struct MoveSheepLabel;
+
+/// The radius of a sheep's body, used for collisions with barriers and the
+/// player.
+pub const SHEEP_RADIUS: f32 = 0.5;
+
+fn move_sheep(
+    mut sheep_query: Query<
+        (&mut Transform, &mut SteeringForce, &mut Speed, &MaxSpeed),
+        With<SheepTag>,
+    >,
+    barrier_query: Query<&Barrier, Without<SheepTag>>,
+    active_config: Res<ActiveFlockingConfig>,
+    timesteps: Res<FixedTimesteps>,
+) {
+    let delta_seconds = step_seconds(&timesteps);
+    sheep_query.iter_mut().for_each(
+        |(mut transform, mut steering, mut speed, max_speed)| {
+            speed.0 = (speed.0 + steering.0 * delta_seconds
+                - active_config.config.drag * speed.0 * delta_seconds)
+                .clamp_length_max(max_speed.0);
+            steering.0 = Vec2::ZERO;
 
             if speed.0.length_squared() > 0.01_f32.powi(2) {
-                transform.translation.x += speed.0.x * time.delta_seconds();
-                transform.translation.z += speed.0.y * time.delta_seconds();
+                let start = ground_position(&transform);
+                let end = barrier_query.iter().fold(
+                    start + speed.0 * delta_seconds,
+                    |end, barrier| {
+                        barrier.constrain_motion(start, end, SHEEP_RADIUS)
+                    },
+                );
+                speed.0 = (end - start) / delta_seconds;
+                transform.translation.x = end.x;
+                transform.translation.z = end.y;
                 transform.rotation =
                     Quat::from_rotation_y(speed.0.angle_between(Vec2::X));
             }
@@ -346,9 +628,138 @@ pub struct SheepPlugin;
 
 impl Plugin for SheepPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(move_sheep.label(MoveSheepLabel))
-            .add_system(player_influence.before(MoveSheepLabel))
-            .add_system(barrier_influence.before(MoveSheepLabel))
-            .add_system(sheep_influences.before(MoveSheepLabel));
+        app.init_resource::<SpatialGrid>()
+            .add_system(apply_flocking_config.label(FlockingConfigLabel))
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                move_sheep
+                    .label(MoveSheepLabel)
+                    .after(SteeringLabel)
+                    .after(MoveDogLabel),
+            )
+            .register_steering::<Avoidance<PlayerTag>>()
+            .register_steering::<Avoidance<WolfTag>>()
+            .register_steering::<Avoidance<DogTag>>()
+            .register_steering::<Avoidance<Barrier>>()
+            .register_steering::<Avoidance<SheepTag>>()
+            .register_steering::<Coalescence<SheepTag>>()
+            .register_steering::<Alignment<SheepTag>>()
+            .register_steering::<Wander>()
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                build_sheep_grid
+                    .label(SheepGridLabel)
+                    .after(MovePlayerLabel)
+                    .after(MoveDogLabel)
+                    .before(PlayerContactLabel)
+                    .before(ShoutScareLabel)
+                    .before(MoveSheepLabel),
+            )
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                avoid_entities::<PlayerTag>
+                    .after(MovePlayerLabel)
+                    .after(SprintLabel)
+                    .after(MoveWolvesLabel)
+                    .before(SteeringLabel),
+            )
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                avoid_entities::<WolfTag>
+                    .after(MoveWolvesLabel)
+                    .before(SteeringLabel),
+            )
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                avoid_entities::<DogTag>
+                    .after(MoveDogLabel)
+                    .after(MoveWolvesLabel)
+                    .before(SteeringLabel),
+            )
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                player_contact
+                    .label(PlayerContactLabel)
+                    .after(MovePlayerLabel)
+                    .after(MoveWolvesLabel)
+                    .before(MoveSheepLabel),
+            )
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                shout_scare
+                    .label(ShoutScareLabel)
+                    .after(ShoutLabel)
+                    .after(MovePlayerLabel)
+                    .after(PlayerContactLabel)
+                    .before(MoveSheepLabel),
+            )
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                drag_hooked_sheep
+                    .after(CrookLabel)
+                    .after(MovePlayerLabel)
+                    .after(SteeringLabel)
+                    .after(PanicLabel)
+                    .after(PlayerContactLabel)
+                    .after(ShoutScareLabel)
+                    .before(MoveSheepLabel),
+            )
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                barrier_influence
+                    .after(MoveWolvesLabel)
+                    .before(SteeringLabel),
+            )
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                wander_influence
+                    .after(MoveWolvesLabel)
+                    .before(SteeringLabel),
+            )
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                sheep_influences
+                    .after(SheepGridLabel)
+                    .after(MoveWolvesLabel)
+                    .after(FrightLabel)
+                    .before(SteeringLabel)
+                    .before(PanicLabel),
+            )
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                threat_panic
+                    .label(FrightLabel)
+                    .after(MovePlayerLabel)
+                    .after(MoveWolvesLabel)
+                    .before(PanicLabel),
+            )
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                update_panic
+                    .label(PanicLabel)
+                    .before(SteeringLabel)
+                    .before(MoveSheepLabel),
+            );
+    }
+}
+
+pub struct SheepRenderPlugin;
+
+impl Plugin for SheepRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(add_sheep_meshes);
     }
 }
@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+/// The name of the fixed timestep on which movement is simulated.
+pub const SIMULATION_TIMESTEP: &str = "simulation";
+
+/// The substage of the simulation timestep in which movement systems run,
+/// between restoring the simulated transforms and recording them again.
+pub const SIMULATION_SUBSTAGE: usize = 1;
+
+const SIMULATION_RATE: f64 = 60.0;
+
+/// The length of the current simulation step, in seconds.
+pub fn step_seconds(timesteps: &FixedTimesteps) -> f32 {
+    timesteps.current().timestep().as_secs_f32()
+}
+
+/// The simulated transform of an entity at the previous and current
+/// simulation steps, between which its rendered transform is interpolated.
+#[derive(Component)]
+pub struct Interpolated {
+    previous: Transform,
+    current: Transform,
+}
+
+impl Interpolated {
+    pub fn new(transform: Transform) -> Self {
+        Self {
+            previous: transform,
+            current: transform,
+        }
+    }
+
+    /// How far `transform` has moved so far in the current simulation step.
+    pub fn step_displacement(&self, transform: &Transform) -> Vec3 {
+        transform.translation - self.previous.translation
+    }
+}
+
+fn begin_step(mut query: Query<(&mut Transform, &mut Interpolated)>) {
+    query
+        .iter_mut()
+        .for_each(|(mut transform, mut interpolated)| {
+            *transform = interpolated.current;
+            interpolated.previous = interpolated.current;
+        })
+}
+
+fn end_step(mut query: Query<(&Transform, &mut Interpolated)>) {
+    query.iter_mut().for_each(|(transform, mut interpolated)| {
+        interpolated.current = *transform;
+    })
+}
+
+fn interpolate(
+    mut query: Query<(&mut Transform, &Interpolated)>,
+    timesteps: Res<FixedTimesteps>,
+) {
+    if let Some(info) = timesteps.get(SIMULATION_TIMESTEP) {
+        let fraction = (info.overstep() as f32).clamp(0_f32, 1_f32);
+        query.iter_mut().for_each(|(mut transform, interpolated)| {
+            transform.translation = interpolated
+                .previous
+                .translation
+                .lerp(interpolated.current.translation, fraction);
+            transform.rotation = interpolated
+                .previous
+                .rotation
+                .slerp(interpolated.current.rotation, fraction);
+        })
+    }
+}
+
+/// Runs movement on a fixed timestep so that it does not depend on frame
+/// rate. Must be added before any plugin registering simulation systems.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_fixed_timestep(
+            Duration::from_secs_f64(1.0 / SIMULATION_RATE),
+            SIMULATION_TIMESTEP,
+        )
+        .add_fixed_timestep_child_stage(SIMULATION_TIMESTEP)
+        .add_fixed_timestep_child_stage(SIMULATION_TIMESTEP)
+        .add_fixed_timestep_system(SIMULATION_TIMESTEP, 0, begin_step)
+        .add_fixed_timestep_system(SIMULATION_TIMESTEP, 2, end_step);
+    }
+}
+
+/// Smooths rendered transforms between simulation steps.
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(interpolate);
+    }
+}
@@ -0,0 +1,131 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    ecs::{schedule::SystemLabelId, system::AsSystemLabel},
+    prelude::*,
+};
+use iyes_loopless::prelude::*;
+
+use crate::flocking::InfluenceConfig;
+use crate::simulation::{SIMULATION_SUBSTAGE, SIMULATION_TIMESTEP};
+
+/// A behaviour which pulls an entity about, gathering its influences during
+/// a simulation step for them to be added to the entity's [`SteeringForce`].
+pub trait Steering: Component {
+    /// The weighted pull gathered since the last call, clearing it.
+    fn take(&mut self) -> Vec2;
+}
+
+/// The combined pull of every steering behaviour on an entity this step, as
+/// an acceleration.
+#[derive(Component, Default)]
+pub struct SteeringForce(pub Vec2);
+
+/// Adds each behaviour's pull to the steering force. Systems gathering
+/// influences run before this, and systems applying the force after it.
+#[derive(SystemLabel)]
+pub struct SteeringLabel;
+
+fn accumulate<S: Steering>(mut query: Query<(&mut S, &mut SteeringForce)>) {
+    query.iter_mut().for_each(|(mut steering, mut force)| {
+        force.0 += steering.take();
+    })
+}
+
+pub trait RegisterSteering {
+    /// Adds the pull of `S` to the steering force of entities with both.
+    fn register_steering<S: Steering>(&mut self) -> &mut Self;
+}
+
+/// The most recently registered steering behaviour. Each behaviour is added
+/// after the one before, so that the force is summed in the same order on
+/// every run and the simulation stays reproducible.
+#[derive(Resource)]
+struct LastSteering(SystemLabelId);
+
+impl RegisterSteering for App {
+    fn register_steering<S: Steering>(&mut self) -> &mut Self {
+        let mut system = accumulate::<S>.label(SteeringLabel);
+        if let Some(last) = self.world.get_resource::<LastSteering>() {
+            system = system.after(last.0);
+        }
+        self.insert_resource(LastSteering(accumulate::<S>.as_system_label()))
+            .add_fixed_timestep_system(
+                SIMULATION_TIMESTEP,
+                SIMULATION_SUBSTAGE,
+                system,
+            )
+    }
+}
+
+/// How the influences of one kind combine into a single pull.
+pub trait InfluenceKind: Send + Sync + 'static {
+    fn combine(influences: &[Vec2]) -> Vec2 {
+        influences.iter().sum()
+    }
+}
+
+/// Pushes away from entities with the source component.
+pub struct Avoid;
+
+impl InfluenceKind for Avoid {}
+
+/// Pulls towards entities with the source component.
+pub struct Coalesce;
+
+impl InfluenceKind for Coalesce {}
+
+/// Matches the mean velocity of entities with the source component.
+pub struct Align;
+
+impl InfluenceKind for Align {
+    fn combine(influences: &[Vec2]) -> Vec2 {
+        if influences.is_empty() {
+            Vec2::ZERO
+        } else {
+            influences.iter().sum::<Vec2>() / influences.len() as f32
+        }
+    }
+}
+
+/// Influences of kind `K` from entities with the component `C`, felt within
+/// `range`.
+#[derive(Component)]
+pub struct Influence<K: InfluenceKind, C: Component> {
+    pub strength: f32,
+    pub range: f32,
+    influences: Vec<Vec2>,
+    marker: PhantomData<(K, C)>,
+}
+
+pub type Avoidance<C> = Influence<Avoid, C>;
+pub type Coalescence<C> = Influence<Coalesce, C>;
+pub type Alignment<C> = Influence<Align, C>;
+
+impl<K: InfluenceKind, C: Component> Influence<K, C> {
+    pub fn new(config: &InfluenceConfig) -> Self {
+        Self {
+            strength: config.strength,
+            range: config.range,
+            influences: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn configure(&mut self, config: &InfluenceConfig) {
+        self.strength = config.strength;
+        self.range = config.range;
+    }
+
+    pub fn push(&mut self, influence: Vec2) {
+        self.influences.push(influence);
+    }
+}
+
+impl<K: InfluenceKind, C: Component> Steering for Influence<K, C> {
+    fn take(&mut self) -> Vec2 {
+        let pull = K::combine(&self.influences) * self.strength;
+        self.influences.clear();
+        pull
+    }
+}
@@ -0,0 +1,61 @@
+//! Small pieces of text kept between sessions: files under the user's config
+//! directory on native builds, and local storage in the browser.
+
+use bevy::prelude::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn config_path(key: &str) -> Option<std::path::PathBuf> {
+    use std::{env, path::PathBuf};
+
+    let directory = env::var_os("XDG_CONFIG_HOME")
+        .or_else(|| env::var_os("APPDATA"))
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+        })?;
+    Some(directory.join("flock").join(format!("{key}.ron")))
+}
+
+/// The text last saved under `key`, if any.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load(key: &str) -> Option<String> {
+    std::fs::read_to_string(config_path(key)?).ok()
+}
+
+/// Saves `contents` under `key`, logging rather than failing if it cannot.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(key: &str, contents: &str) {
+    let Some(path) = config_path(key) else {
+        warn!("No config directory to save {} in", key);
+        return;
+    };
+    if let Err(error) = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, contents))
+    {
+        warn!("Could not save {}: {}", path.display(), error);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// The text last saved under `key`, if any.
+#[cfg(target_arch = "wasm32")]
+pub fn load(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+/// Saves `contents` under `key`, logging rather than failing if it cannot.
+#[cfg(target_arch = "wasm32")]
+pub fn save(key: &str, contents: &str) {
+    if local_storage()
+        .and_then(|storage| storage.set_item(key, contents).ok())
+        .is_none()
+    {
+        warn!("Could not save {} to local storage", key);
+    }
+}
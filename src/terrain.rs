@@ -15,10 +15,25 @@ impl TerrainPlugin {
             ..default()
         });
     }
+
+    fn create_sun(mut commands: Commands) {
+        commands.spawn(DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                color: Color::WHITE,
+                illuminance: 50000_f32,
+                shadows_enabled: true,
+                ..default()
+            },
+            transform: Transform::from_xyz(-100_f32, 50_f32, -50_f32)
+                .looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        });
+    }
 }
 
 impl Plugin for TerrainPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(TerrainPlugin::create_land);
+        app.add_startup_system(TerrainPlugin::create_land)
+            .add_startup_system(TerrainPlugin::create_sun);
     }
 }
@@ -0,0 +1,276 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use iyes_loopless::prelude::*;
+
+use crate::barrier::Barrier;
+use crate::common::{ground_position, RoundScoped, Threat};
+use crate::dog::MoveDogLabel;
+use crate::grid::SpatialGrid;
+use crate::player::{MovePlayerLabel, PlayerTag};
+use crate::sheep::{SheepGridLabel, SheepTag, SHEEP_RADIUS};
+use crate::simulation::{
+    step_seconds, Interpolated, SIMULATION_SUBSTAGE, SIMULATION_TIMESTEP,
+};
+
+/// The radius of a wolf's body, used for collisions with barriers and for
+/// catching sheep.
+const WOLF_RADIUS: f32 = 0.6;
+
+const STALK_SPEED: f32 = 4.0;
+const HUNT_SPEED: f32 = 10.5;
+const FLEE_SPEED: f32 = 12.0;
+
+/// How far outside the flock a stalking wolf circles.
+const STALK_MARGIN: f32 = 10.0;
+/// A sheep with no other sheep this close is a straggler, and fair game.
+const STRAGGLER_DISTANCE: f32 = 8.0;
+/// A wolf gives up on a straggler once it has this many sheep about it.
+const RESCUE_COUNT: usize = 2;
+/// A wolf bolts when the shepherd comes this close...
+const FLEE_RANGE: f32 = 12.0;
+/// ...and keeps running until the shepherd is this far away.
+const SAFE_RANGE: f32 = 25.0;
+/// How long, in seconds, a wolf lies low after catching a sheep.
+const REST_TIME: f32 = 5.0;
+
+#[derive(Component)]
+pub struct WolfTag;
+
+#[derive(Clone, Copy, PartialEq)]
+enum WolfMode {
+    /// Circling the edge of the flock, looking for a straggler.
+    Stalking,
+    /// Running down a straggler.
+    Hunting(Entity),
+    /// Running from the shepherd.
+    Fleeing,
+    /// Lying low after a catch, for the remaining seconds.
+    Resting(f32),
+}
+
+#[derive(Component)]
+struct Wolf {
+    mode: WolfMode,
+}
+
+#[derive(Bundle)]
+pub struct WolfBundle {
+    tag: WolfTag,
+    wolf: Wolf,
+    threat: Threat,
+    round: RoundScoped,
+    #[bundle]
+    transform: TransformBundle,
+    interpolated: Interpolated,
+}
+
+impl WolfBundle {
+    fn new(position: Vec2) -> Self {
+        let transform = Transform::from_xyz(position.x, 0_f32, position.y);
+        Self {
+            tag: WolfTag,
+            wolf: Wolf {
+                mode: WolfMode::Stalking,
+            },
+            threat: Threat,
+            round: RoundScoped,
+            transform: TransformBundle::from_transform(transform),
+            interpolated: Interpolated::new(transform),
+        }
+    }
+
+    pub fn spawn(commands: &mut Commands, position: Vec2) {
+        commands.spawn(WolfBundle::new(position));
+    }
+}
+
+fn add_wolf_meshes(
+    mut commands: Commands,
+    wolf_query: Query<Entity, Added<WolfTag>>,
+    mut mesh_assets: ResMut<Assets<Mesh>>,
+    mut standard_material_assets: ResMut<Assets<StandardMaterial>>,
+) {
+    wolf_query.iter().for_each(|entity| {
+        commands.entity(entity).insert((
+            mesh_assets.add(Mesh::from(shape::Box {
+                min_x: -0.7,
+                max_x: 0.7,
+                min_y: 0.0,
+                max_y: 0.7,
+                min_z: -0.3,
+                max_z: 0.3,
+            })),
+            standard_material_assets
+                .add(StandardMaterial::from(Color::hsl(0.0, 0.0, 0.25))),
+            VisibilityBundle::default(),
+        ));
+    });
+}
+
+/// Whether the sheep at `position` has fewer than `count` other sheep
+/// within straggling distance.
+fn is_isolated(
+    grid: &SpatialGrid,
+    entity: Entity,
+    position: Vec2,
+    count: usize,
+) -> bool {
+    grid.neighbours(position, STRAGGLER_DISTANCE)
+        .filter(|neighbour| neighbour.entity != entity)
+        .nth(count - 1)
+        .is_none()
+}
+
+/// The velocity which keeps a wolf circling the flock at a distance.
+fn stalk_velocity(position: Vec2, centre: Vec2, flock_radius: f32) -> Vec2 {
+    let offset = position - centre;
+    let outward = offset.try_normalize().unwrap_or(Vec2::X);
+    let tangent = Vec2::new(-outward.y, outward.x);
+    let radial = flock_radius + STALK_MARGIN - offset.length();
+    (tangent * STALK_SPEED + outward * radial).clamp_length_max(STALK_SPEED)
+}
+
+#[derive(SystemLabel)]
+pub struct MoveWolvesLabel;
+
+#[allow(clippy::type_complexity)]
+fn move_wolves(
+    mut commands: Commands,
+    mut wolf_query: Query<
+        (&mut Wolf, &mut Transform),
+        (With<WolfTag>, Without<SheepTag>, Without<PlayerTag>),
+    >,
+    sheep_query: Query<(Entity, &Transform), With<SheepTag>>,
+    player_query: Query<&Transform, With<PlayerTag>>,
+    barrier_query: Query<&Barrier>,
+    grid: Res<SpatialGrid>,
+    timesteps: Res<FixedTimesteps>,
+) {
+    let delta_seconds = step_seconds(&timesteps);
+    let sheep_count = sheep_query.iter().count();
+    let centre = sheep_query
+        .iter()
+        .map(|(_, transform)| ground_position(transform))
+        .sum::<Vec2>()
+        / sheep_count.max(1) as f32;
+    let flock_radius = sheep_query
+        .iter()
+        .map(|(_, transform)| ground_position(transform).distance(centre))
+        .fold(0_f32, f32::max);
+    let mut caught = HashSet::new();
+
+    wolf_query.iter_mut().for_each(|(mut wolf, mut transform)| {
+        let position = ground_position(&transform);
+        let nearest_player =
+            player_query.iter().map(ground_position).min_by(|a, b| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            });
+        let player_distance = nearest_player
+            .map_or(f32::INFINITY, |player| player.distance(position));
+
+        wolf.mode = match wolf.mode {
+            _ if player_distance < FLEE_RANGE => WolfMode::Fleeing,
+            WolfMode::Fleeing if player_distance < SAFE_RANGE => {
+                WolfMode::Fleeing
+            }
+            WolfMode::Resting(remaining) if remaining > delta_seconds => {
+                WolfMode::Resting(remaining - delta_seconds)
+            }
+            WolfMode::Hunting(target)
+                if sheep_query.get(target).is_ok_and(|(_, sheep)| {
+                    is_isolated(
+                        &grid,
+                        target,
+                        ground_position(sheep),
+                        RESCUE_COUNT,
+                    )
+                }) =>
+            {
+                WolfMode::Hunting(target)
+            }
+            _ => sheep_query
+                .iter()
+                .filter(|(entity, sheep)| {
+                    !caught.contains(entity)
+                        && is_isolated(
+                            &grid,
+                            *entity,
+                            ground_position(sheep),
+                            1,
+                        )
+                })
+                .min_by(|(_, a), (_, b)| {
+                    ground_position(a).distance_squared(position).total_cmp(
+                        &ground_position(b).distance_squared(position),
+                    )
+                })
+                .map_or(WolfMode::Stalking, |(entity, _)| {
+                    WolfMode::Hunting(entity)
+                }),
+        };
+
+        let velocity = match wolf.mode {
+            WolfMode::Fleeing => nearest_player.map_or(Vec2::ZERO, |player| {
+                (position - player).normalize_or_zero() * FLEE_SPEED
+            }),
+            WolfMode::Resting(_) => Vec2::ZERO,
+            WolfMode::Stalking if sheep_count > 0 => {
+                stalk_velocity(position, centre, flock_radius)
+            }
+            WolfMode::Stalking => Vec2::ZERO,
+            WolfMode::Hunting(target) => {
+                let Ok((_, sheep)) = sheep_query.get(target) else {
+                    return;
+                };
+                let offset = ground_position(sheep) - position;
+                if offset.length() < WOLF_RADIUS + SHEEP_RADIUS
+                    && caught.insert(target)
+                {
+                    commands.entity(target).despawn_recursive();
+                    wolf.mode = WolfMode::Resting(REST_TIME);
+                    Vec2::ZERO
+                } else {
+                    offset.clamp_length_max(HUNT_SPEED * delta_seconds)
+                        / delta_seconds
+                }
+            }
+        };
+
+        let end = barrier_query
+            .iter()
+            .fold(position + velocity * delta_seconds, |end, barrier| {
+                barrier.constrain_motion(position, end, WOLF_RADIUS)
+            });
+        transform.translation.x = end.x;
+        transform.translation.z = end.y;
+        if velocity != Vec2::ZERO {
+            transform.rotation =
+                Quat::from_rotation_y(velocity.angle_between(Vec2::X));
+        }
+    })
+}
+
+pub struct WolfPlugin;
+
+impl Plugin for WolfPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_fixed_timestep_system(
+            SIMULATION_TIMESTEP,
+            SIMULATION_SUBSTAGE,
+            move_wolves
+                .label(MoveWolvesLabel)
+                .after(SheepGridLabel)
+                .after(MovePlayerLabel)
+                .after(MoveDogLabel),
+        );
+    }
+}
+
+pub struct WolfRenderPlugin;
+
+impl Plugin for WolfRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(add_wolf_meshes);
+    }
+}
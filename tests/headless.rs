@@ -0,0 +1,148 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bevy::ecs::schedule::ReportExecutionOrderAmbiguities;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy::utils::tracing::subscriber::with_default;
+use iyes_loopless::prelude::CurrentState;
+use my_game::{headless_app, GameState, LevelSeed, SheepTag};
+
+const SEED: u64 = 1234;
+const FRAMES: u32 = 600;
+
+fn seeded_app(seed: u64) -> App {
+    let mut app = headless_app();
+    app.insert_resource(LevelSeed(seed));
+    app
+}
+
+/// Steps the app, advancing its clock by exactly one simulation step per
+/// update however fast the test runs.
+fn run(app: &mut App, frames: u32) {
+    let start = Instant::now();
+    (1..=frames).for_each(|frame| {
+        app.insert_resource(TimeUpdateStrategy::ManualInstant(
+            start + Duration::from_secs_f64(frame as f64 / 60.0),
+        ));
+        app.update();
+    })
+}
+
+fn sheep_positions(app: &mut App) -> Vec<(Entity, Vec3)> {
+    let mut positions = app
+        .world
+        .query_filtered::<(Entity, &Transform), With<SheepTag>>()
+        .iter(&app.world)
+        .map(|(entity, transform)| (entity, transform.translation))
+        .collect::<Vec<_>>();
+    positions.sort_by_key(|(entity, _)| *entity);
+    positions
+}
+
+#[test]
+fn sheep_move_while_playing() {
+    let mut app = seeded_app(SEED);
+    run(&mut app, 2);
+    let start = sheep_positions(&mut app);
+    assert!(!start.is_empty());
+
+    run(&mut app, FRAMES);
+    let end = sheep_positions(&mut app);
+
+    assert_eq!(start.len(), end.len());
+    assert!(start
+        .iter()
+        .zip(&end)
+        .any(|((_, before), (_, after))| before.distance(*after) > 0.1));
+    assert_eq!(
+        app.world.resource::<CurrentState<GameState>>().0,
+        GameState::Playing
+    );
+}
+
+#[test]
+fn same_seed_same_positions() {
+    let mut first = seeded_app(SEED);
+    let mut second = seeded_app(SEED);
+    run(&mut first, FRAMES);
+    run(&mut second, FRAMES);
+
+    let first = sheep_positions(&mut first);
+    let second = sheep_positions(&mut second);
+    assert!(!first.is_empty());
+    assert_eq!(first, second);
+}
+
+#[test]
+fn losing_every_sheep_fails_the_round() {
+    let mut app = seeded_app(SEED);
+    run(&mut app, 2);
+    let start = sheep_positions(&mut app);
+    start.iter().for_each(|(entity, _)| {
+        app.world.despawn(*entity);
+    });
+
+    run(&mut app, 2);
+    assert_eq!(
+        app.world.resource::<CurrentState<GameState>>().0,
+        GameState::Failure
+    );
+
+    // The same round is played again once the interlude is over.
+    run(&mut app, 240);
+    assert_eq!(
+        app.world.resource::<CurrentState<GameState>>().0,
+        GameState::Playing
+    );
+    let replayed = sheep_positions(&mut app);
+    assert_eq!(replayed.len(), start.len());
+}
+
+/// Collects what the app logs, for tests that check its warnings.
+#[derive(Clone, Default)]
+struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for LogBuffer {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn systems_run_in_a_fixed_order() {
+    let log = LogBuffer::default();
+    let writer = log.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    with_default(subscriber, || {
+        let mut app = seeded_app(SEED);
+        app.insert_resource(ReportExecutionOrderAmbiguities);
+        // The fixed timestep stages only report once a step has run.
+        run(&mut app, 10);
+    });
+
+    let log = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+    assert!(!log.contains("Execution order ambiguities"), "{log}");
+}
+
+#[test]
+fn no_round_starts_already_won() {
+    (0..64).for_each(|seed| {
+        let mut app = seeded_app(seed);
+        run(&mut app, 5);
+        assert_eq!(
+            app.world.resource::<CurrentState<GameState>>().0,
+            GameState::Playing,
+            "seed {seed}"
+        );
+    })
+}